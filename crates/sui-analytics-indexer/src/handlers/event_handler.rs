@@ -67,12 +67,12 @@ impl AnalyticsHandler<EventEntry> for EventHandler {
 }
 
 impl EventHandler {
-    pub fn new(store_path: &Path, rest_uri: &str) -> Self {
-        let store = LocalDBPackageStore::new(&store_path.join("event"), rest_uri);
-        EventHandler {
+    pub fn new(store_path: &Path, rest_uri: &str) -> Result<Self> {
+        let store = LocalDBPackageStore::new(&store_path.join("event"), rest_uri)?;
+        Ok(EventHandler {
             events: vec![],
             resolver: Resolver::new(store),
-        }
+        })
     }
     async fn process_events(
         &mut self,
@@ -68,12 +68,12 @@ impl AnalyticsHandler<ObjectEntry> for ObjectHandler {
 }
 
 impl ObjectHandler {
-    pub fn new(store_path: &Path, rest_uri: &str) -> Self {
-        let store = LocalDBPackageStore::new(&store_path.join("object"), rest_uri);
-        ObjectHandler {
+    pub fn new(store_path: &Path, rest_uri: &str) -> Result<Self> {
+        let store = LocalDBPackageStore::new(&store_path.join("object"), rest_uri)?;
+        Ok(ObjectHandler {
             objects: vec![],
             resolver: Resolver::new(store),
-        }
+        })
     }
     async fn process_transaction(
         &mut self,
@@ -424,7 +424,7 @@ pub async fn make_object_processor(
     let handler: Box<dyn AnalyticsHandler<ObjectEntry>> = Box::new(ObjectHandler::new(
         &config.package_cache_path,
         &config.rest_url,
-    ));
+    )?);
     let starting_checkpoint_seq_num =
         get_starting_checkpoint_seq_num(config.clone(), FileType::Object).await?;
     let writer = make_writer::<ObjectEntry>(
@@ -449,7 +449,7 @@ pub async fn make_event_processor(
     let handler: Box<dyn AnalyticsHandler<EventEntry>> = Box::new(EventHandler::new(
         &config.package_cache_path,
         &config.rest_url,
-    ));
+    )?);
     let starting_checkpoint_seq_num =
         get_starting_checkpoint_seq_num(config.clone(), FileType::Event).await?;
     let writer =
@@ -70,12 +70,12 @@ pub struct LocalDBPackageStore {
 }
 
 impl LocalDBPackageStore {
-    pub fn new(path: &Path, rest_url: &str) -> Self {
+    pub fn new(path: &Path, rest_url: &str) -> anyhow::Result<Self> {
         let rest_api_url = format!("{}/rest", rest_url);
-        Self {
+        Ok(Self {
             package_store_tables: PackageStoreTables::new(path),
-            fallback_client: Client::new(rest_api_url),
-        }
+            fallback_client: Client::new(rest_api_url)?,
+        })
     }
 
     pub fn update(&self, object: &Object) -> Result<()> {
@@ -16,6 +16,8 @@ const MAX_QUERY_PAYLOAD_SIZE: u32 = 5_000;
 const MAX_DB_QUERY_COST: u64 = 20_000; // Max DB query cost (normally f64) truncated
 const DEFAULT_PAGE_SIZE: u64 = 20; // Default number of elements allowed on a page of a connection
 const MAX_PAGE_SIZE: u64 = 50; // Maximum number of elements allowed on a page of a connection
+const MAX_EVENT_FILTER_DEPTH: u32 = 5; // Maximum nesting of `all`, `any` and `not` event filters
+const MAX_EVENT_FILTER_NODES: u32 = 50; // Maximum number of filters in one event filter
 
 const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 40_000;
 
@@ -72,6 +74,10 @@ pub struct Limits {
     pub(crate) max_page_size: u64,
     #[serde(default)]
     pub(crate) request_timeout_ms: u64,
+    #[serde(default)]
+    pub(crate) max_event_filter_depth: u32,
+    #[serde(default)]
+    pub(crate) max_event_filter_nodes: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
@@ -198,6 +204,16 @@ impl ServiceConfig {
     async fn max_query_payload_size(&self) -> u32 {
         self.limits.max_query_payload_size
     }
+
+    /// Maximum number of levels of filters in an event filter, nested in `all`, `any` or `not`.
+    async fn max_event_filter_depth(&self) -> u32 {
+        self.limits.max_event_filter_depth
+    }
+
+    /// Maximum number of filters in an event filter, counting every nested filter.
+    async fn max_event_filter_nodes(&self) -> u32 {
+        self.limits.max_event_filter_nodes
+    }
 }
 
 impl Default for ConnectionConfig {
@@ -223,6 +239,8 @@ impl Default for Limits {
             default_page_size: DEFAULT_PAGE_SIZE,
             max_page_size: MAX_PAGE_SIZE,
             request_timeout_ms: DEFAULT_REQUEST_TIMEOUT_MS,
+            max_event_filter_depth: MAX_EVENT_FILTER_DEPTH,
+            max_event_filter_nodes: MAX_EVENT_FILTER_NODES,
         }
     }
 }
@@ -319,6 +337,8 @@ mod tests {
                 default-page-size = 20
                 max-page-size = 50
                 request-timeout-ms = 27000
+                max-event-filter-depth = 3
+                max-event-filter-nodes = 10
             "#,
         )
         .unwrap();
@@ -332,6 +352,8 @@ mod tests {
                 default_page_size: 20,
                 max_page_size: 50,
                 request_timeout_ms: 27_000,
+                max_event_filter_depth: 3,
+                max_event_filter_nodes: 10,
             },
             ..Default::default()
         };
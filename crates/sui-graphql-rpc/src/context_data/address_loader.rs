@@ -0,0 +1,83 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, convert::Infallible};
+
+use async_graphql::dataloader::Loader;
+use async_trait::async_trait;
+
+use crate::types::{address::Address, sui_address::SuiAddress};
+
+/// Resolves addresses into `Address` values, so that resolvers returning addresses for every
+/// element of a list (e.g. the senders of a page of events) are served in a single batch, rather
+/// than one lookup per element.
+#[derive(Default)]
+pub(crate) struct AddressLoader;
+
+#[async_trait]
+impl Loader<SuiAddress> for AddressLoader {
+    type Value = Address;
+    type Error = Infallible;
+
+    async fn load(&self, keys: &[SuiAddress]) -> Result<HashMap<SuiAddress, Address>, Infallible> {
+        Ok(keys
+            .iter()
+            .map(|address| (*address, Address { address: *address }))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use async_graphql::{dataloader::DataLoader, EmptyMutation, EmptySubscription, Object, Schema};
+
+    use crate::types::event::Event;
+
+    use super::*;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        /// Events emitted by three senders between them.
+        async fn events(&self) -> Vec<Event> {
+            (0..10u8)
+                .map(|i| Event {
+                    sending_module_id: None,
+                    event_type: None,
+                    sender_addresses: Some(vec![SuiAddress::from_array([i % 3; 32])]),
+                    timestamp_ms: None,
+                    json: None,
+                    bcs: None,
+                    transaction_digest: None,
+                    sequence_number: None,
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_senders_are_loaded_in_one_batch() {
+        // The loader spawns a task for every batch of addresses it loads.
+        let batches = Arc::new(AtomicUsize::new(0));
+        let spawned = batches.clone();
+        let loader = DataLoader::new(AddressLoader, move |batch| {
+            spawned.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(batch)
+        });
+
+        let response = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data(loader)
+            .finish()
+            .execute("{ events { senders { location } } }")
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(batches.load(Ordering::Relaxed), 1);
+    }
+}
@@ -3,17 +3,17 @@
 
 use diesel::backend::Backend;
 use sui_indexer::{
-    schema_v2::{checkpoints, epochs, objects, transactions},
+    schema_v2::{checkpoints, epochs, events, objects, transactions},
     types_v2::OwnerType,
 };
 
 use crate::{
     error::Error,
-    types::{object::ObjectFilter, transaction_block::TransactionBlockFilter},
+    types::{event::EventFilter, object::ObjectFilter, transaction_block::TransactionBlockFilter},
 };
 use diesel::{
     query_builder::{BoxedSelectStatement, FromClause, QueryId},
-    sql_types::Text,
+    sql_types::{BigInt, Bytea, Text},
 };
 
 pub(crate) type BalanceQuery<'a, DB> = BoxedSelectStatement<
@@ -28,6 +28,19 @@ pub(crate) type BalanceQuery<'a, DB> = BoxedSelectStatement<
     objects::dsl::coin_type,
 >;
 
+/// Counts the events matching a filter.
+pub(crate) type EventCountQuery<'a, DB> =
+    BoxedSelectStatement<'a, BigInt, FromClause<events::table>, DB>;
+
+/// Counts the events matching a filter, per package and module that emitted them.
+pub(crate) type ModuleEventCountQuery<'a, DB> = BoxedSelectStatement<
+    'a,
+    (Bytea, Text, BigInt),
+    FromClause<events::table>,
+    DB,
+    (events::dsl::package, events::dsl::module),
+>;
+
 pub(crate) trait GenericQueryBuilder<DB: Backend> {
     fn get_tx_by_digest(digest: Vec<u8>) -> transactions::BoxedQuery<'static, DB>;
     fn get_obj(address: Vec<u8>, version: Option<i64>) -> objects::BoxedQuery<'static, DB>;
@@ -69,6 +82,16 @@ pub(crate) trait GenericQueryBuilder<DB: Backend> {
         limit: i64,
         epoch: Option<i64>,
     ) -> checkpoints::BoxedQuery<'static, DB>;
+    fn multi_get_events(
+        cursor: Option<(i64, i64, i64, i64)>,
+        descending_order: bool,
+        limit: i64,
+        filter: EventFilter,
+    ) -> Result<events::BoxedQuery<'static, DB>, Error>;
+    fn count_events(filter: EventFilter) -> Result<EventCountQuery<'static, DB>, Error>;
+    fn count_events_by_emitting_module(
+        filter: EventFilter,
+    ) -> Result<ModuleEventCountQuery<'static, DB>, Error>;
 }
 
 /// Struct for custom diesel function
@@ -18,7 +18,7 @@ use crate::{
         dynamic_field::{DynamicField, DynamicFieldName},
         end_of_epoch_data::EndOfEpochData,
         epoch::Epoch,
-        event::{Event, EventFilter},
+        event::{Event, EventFilter, EventSortOrder, ModuleEventCount},
         gas::{GasCostSummary, GasInput},
         move_module::MoveModuleId,
         move_object::MoveObject,
@@ -47,14 +47,20 @@ use crate::{
 };
 use async_graphql::connection::{Connection, Edge};
 use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
-use move_core_types::language_storage::StructTag;
-use std::str::FromStr;
+use fastcrypto::encoding::{Base64 as FastCryptoBase64, Encoding};
+use move_core_types::{
+    annotated_value::{MoveStruct, MoveStructLayout},
+    language_storage::StructTag,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
 use sui_indexer::{
     apis::GovernanceReadApiV2,
+    errors::IndexerError,
     indexer_reader::IndexerReader,
     models_v2::{
-        checkpoints::StoredCheckpoint, epoch::StoredEpochInfo, objects::StoredObject,
-        transactions::StoredTransaction,
+        checkpoints::StoredCheckpoint, epoch::StoredEpochInfo, events::StoredEvent,
+        objects::StoredObject, transactions::StoredTransaction,
     },
     schema_v2::transactions,
     types_v2::OwnerType,
@@ -65,8 +71,7 @@ use sui_json_rpc::{
     name_service::{Domain, NameRecord, NameServiceConfig},
 };
 use sui_json_rpc_types::{
-    EventFilter as RpcEventFilter, ProtocolConfigResponse, Stake as RpcStakedSui,
-    SuiTransactionBlockEffects,
+    ProtocolConfigResponse, Stake as RpcStakedSui, SuiMoveStruct, SuiTransactionBlockEffects,
 };
 use sui_protocol_config::{ProtocolConfig, ProtocolVersion};
 use sui_types::{
@@ -77,20 +82,20 @@ use sui_types::{
     digests::TransactionDigest,
     dynamic_field::{DynamicFieldType, Field},
     effects::TransactionEffects,
-    event::EventID,
     gas_coin::{GAS, TOTAL_SUPPLY_SUI},
     governance::StakedSui as NativeStakedSui,
     messages_checkpoint::{
         CheckpointCommitment, CheckpointDigest, EndOfEpochData as NativeEndOfEpochData,
     },
-    object::Object as NativeObject,
+    object::{MoveObject as NativeMoveObject, Object as NativeObject},
+    parse_sui_struct_tag,
     sui_system_state::sui_system_state_summary::{
         SuiSystemStateSummary as NativeSuiSystemStateSummary, SuiValidatorSummary,
     },
     transaction::{
         GenesisObject, SenderSignedData, TransactionDataAPI, TransactionExpiration, TransactionKind,
     },
-    Identifier, TypeTag,
+    TypeTag,
 };
 
 use super::db_backend::GenericQueryBuilder;
@@ -124,6 +129,139 @@ pub enum DbValidationError {
     QueryCostExceeded(u64, u64),
     #[error("Page size exceeded - requested: {0}, limit: {1}")]
     PageSizeExceeded(u64, u64),
+    #[error("Start time must not be after end time")]
+    InvalidTimeRange,
+    #[error("'any' event filter requires at least one filter")]
+    EmptyAnyFilter,
+    #[error("Event filter is contradicted by its 'not' filter and can never match")]
+    ContradictoryEventFilter,
+    #[error("invalid transaction digest")]
+    InvalidTransactionDigest,
+    #[error("Invalid event type '{0}': expected a Move struct type such as 0x2::coin::CurrencyCreated or 0x2::coin::CurrencyCreated<0x2::sui::SUI>")]
+    InvalidEventType(String),
+    #[error("'emittingModule' requires 'emittingPackage'")]
+    EmittingModuleRequiresPackage,
+    #[error("'eventModule' requires 'eventPackage'")]
+    EventModuleRequiresPackage,
+    #[error("Event filter depth exceeded - depth: {0}, limit: {1}")]
+    EventFilterDepthExceeded(u32, u32),
+    #[error("Event filter size exceeded - filters: {0}, limit: {1}")]
+    EventFilterNodesExceeded(u32, u32),
+}
+
+/// Position of an event in the total order of events, exposed to clients as an opaque cursor.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct EventCursor {
+    pub timestamp_ms: i64,
+    pub checkpoint_sequence_number: i64,
+    pub tx_sequence_number: i64,
+    pub event_sequence_number: i64,
+}
+
+impl EventCursor {
+    fn new(event: &StoredEvent) -> Self {
+        Self {
+            timestamp_ms: event.timestamp_ms,
+            checkpoint_sequence_number: event.checkpoint_sequence_number,
+            tx_sequence_number: event.tx_sequence_number,
+            event_sequence_number: event.event_sequence_number,
+        }
+    }
+
+    fn encode(&self) -> String {
+        FastCryptoBase64::encode(bcs::to_bytes(self).unwrap())
+    }
+
+    fn decode(cursor: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidCursor("event".to_string());
+        let Base64(bytes) = Base64::from_str(cursor).map_err(|_| invalid())?;
+        bcs::from_bytes(&bytes).map_err(|_| invalid())
+    }
+}
+
+/// Layouts of event types, resolved at most once per type, so that converting a page of events of
+/// the same type only resolves that type's layout once.
+struct EventLayoutCache<F> {
+    resolve: F,
+    layouts: HashMap<StructTag, MoveStructLayout>,
+}
+
+impl<F> EventLayoutCache<F>
+where
+    F: FnMut(&StructTag) -> Result<MoveStructLayout, IndexerError>,
+{
+    fn new(resolve: F) -> Self {
+        Self {
+            resolve,
+            layouts: HashMap::new(),
+        }
+    }
+
+    fn layout(&mut self, type_: &StructTag) -> Result<MoveStructLayout, IndexerError> {
+        if let Some(layout) = self.layouts.get(type_) {
+            return Ok(layout.clone());
+        }
+
+        let layout = (self.resolve)(type_)?;
+        self.layouts.insert(type_.clone(), layout.clone());
+        Ok(layout)
+    }
+}
+
+/// Converts an event as stored by the indexer. Its contents are only deserialized, and the layout
+/// of its type only resolved, if they were stored: Move structs serialize to at least one byte, so
+/// no bytes means the contents were not stored.
+fn event_from_stored<F>(
+    stored: StoredEvent,
+    layouts: &mut EventLayoutCache<F>,
+) -> Result<Event, IndexerError>
+where
+    F: FnMut(&StructTag) -> Result<MoveStructLayout, IndexerError>,
+{
+    let corrupt = |what: &str, e: &dyn std::fmt::Display| {
+        IndexerError::PersistentStorageDataCorruptionError(format!(
+            "Failed to parse event {what}: {e}"
+        ))
+    };
+
+    let package = SuiAddress::from_bytes(&stored.package).map_err(|e| corrupt("package", &e))?;
+    // Stored sender addresses are not guaranteed to be padded to their full length
+    let senders = stored
+        .senders
+        .iter()
+        .flatten()
+        .map(|sender| {
+            SuiAddress::from_short_bytes(sender).map_err(|e| corrupt("sender address", &e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let type_ = parse_sui_struct_tag(&stored.event_type)?;
+    let tx_digest = TransactionDigest::try_from(stored.transaction_digest.as_slice())
+        .map_err(|e| corrupt("transaction digest", &e))?;
+
+    let json = if stored.bcs.is_empty() {
+        None
+    } else {
+        let layout = layouts.layout(&type_)?;
+        let contents = MoveStruct::simple_deserialize(&stored.bcs, &layout)
+            .map_err(|e| IndexerError::SerdeError(e.to_string()))?;
+        Some(SuiMoveStruct::from(contents).to_json_value().to_string())
+    };
+
+    Ok(Event {
+        sending_module_id: Some(MoveModuleId {
+            package,
+            name: stored.module,
+        }),
+        event_type: Some(MoveType::new(
+            type_.to_canonical_string(/* with_prefix */ true),
+        )),
+        sender_addresses: Some(senders),
+        timestamp_ms: Some(stored.timestamp_ms as u64),
+        json,
+        bcs: (!stored.bcs.is_empty()).then(|| Base64::from(stored.bcs)),
+        transaction_digest: Some(tx_digest.base58_encode()),
+        sequence_number: Some(stored.event_sequence_number as u64),
+    })
 }
 
 pub(crate) struct PgManager {
@@ -481,6 +619,97 @@ impl PgManager {
             })
             .transpose()
     }
+
+    async fn multi_get_events(
+        &self,
+        first: Option<u64>,
+        after: Option<String>,
+        last: Option<u64>,
+        before: Option<String>,
+        filter: EventFilter,
+        sort: EventSortOrder,
+    ) -> Result<Option<(Vec<StoredEvent>, bool, bool)>, Error> {
+        let limit = self.validate_page_limit(first, last)?;
+        // Paginating backwards walks the events in the opposite order to the one they are sorted in
+        let descending_order = sort.is_descending() != last.is_some();
+        // Events are paginated on their timestamp, then their checkpoint, then the sequence number
+        // of their transaction, then their position in it
+        let cursor = after
+            .or(before)
+            .map(|cursor| EventCursor::decode(&cursor))
+            .transpose()?
+            .map(|cursor| {
+                (
+                    cursor.timestamp_ms,
+                    cursor.checkpoint_sequence_number,
+                    cursor.tx_sequence_number,
+                    cursor.event_sequence_number,
+                )
+            });
+
+        let page_filter = filter.clone();
+        let query = move || {
+            QueryBuilder::multi_get_events(cursor, descending_order, limit, page_filter.clone())
+        };
+
+        let result: Option<Vec<StoredEvent>> = self
+            .run_query_async_with_cost(query, |query| move |conn| query.load(conn).optional())
+            .await?;
+        let Some(mut stored_events) = result else {
+            return Ok(None);
+        };
+
+        let has_more = stored_events.len() as i64 > limit;
+        if has_more {
+            stored_events.pop();
+        }
+
+        // Whether any event matching the filter lies behind the page: at the cursor or beyond it,
+        // walking away from the page. Event sequence numbers are integers, so stepping the cursor
+        // one event past itself makes it inclusive.
+        let has_behind = match cursor {
+            None => false,
+            Some((timestamp_ms, checkpoint, tx_seq, event_seq)) => {
+                let past = if descending_order {
+                    event_seq - 1
+                } else {
+                    event_seq + 1
+                };
+                let cursor = Some((timestamp_ms, checkpoint, tx_seq, past));
+                let query = move || {
+                    QueryBuilder::multi_get_events(cursor, !descending_order, 0, filter.clone())
+                };
+
+                let behind: Option<Vec<StoredEvent>> = self
+                    .run_query_async_with_cost(query, |query| {
+                        move |conn| query.load(conn).optional()
+                    })
+                    .await?;
+                behind.is_some_and(|events| !events.is_empty())
+            }
+        };
+
+        Ok(Some((stored_events, has_more, has_behind)))
+    }
+
+    async fn count_events(&self, filter: EventFilter) -> Result<i64, Error> {
+        self.run_query_async_with_cost(
+            move || QueryBuilder::count_events(filter.clone()),
+            |query| move |conn| query.get_result(conn),
+        )
+        .await
+    }
+
+    async fn count_events_by_emitting_module(
+        &self,
+        filter: EventFilter,
+    ) -> Result<Vec<(Vec<u8>, String, i64)>, Error> {
+        self.run_query_async_with_cost(
+            move || QueryBuilder::count_events_by_emitting_module(filter.clone()),
+            |query| move |conn| query.load(conn),
+        )
+        .await
+    }
 }
 
 /// Implement methods to be used by graphql resolvers
@@ -505,10 +734,6 @@ impl PgManager {
         Ok(sequence_number)
     }
 
-    pub(crate) fn parse_event_cursor(&self, cursor: String) -> Result<EventID, Error> {
-        EventID::try_from(cursor).map_err(|_| Error::InvalidCursor("event".to_string()))
-    }
-
     pub(crate) fn validate_package_dependencies(
         &self,
         package: Option<&SuiAddress>,
@@ -1193,82 +1418,89 @@ impl PgManager {
         last: Option<u64>,
         before: Option<String>,
         filter: EventFilter,
+        sort: EventSortOrder,
     ) -> Result<Option<Connection<String, Event>>, Error> {
-        let event_filter: Result<RpcEventFilter, Error> = if let Some(sender) = filter.sender {
-            let sender = NativeSuiAddress::from_bytes(sender.into_array())
-                .map_err(|_| Error::InvalidFilter)?;
-            Ok(RpcEventFilter::Sender(sender))
-        } else if let Some(digest) = filter.transaction_digest {
-            let digest = TransactionDigest::from_str(&digest).map_err(|_| Error::InvalidFilter)?;
-            Ok(RpcEventFilter::Transaction(digest))
-        } else if let Some(package) = filter.emitting_package {
-            if let Some(module) = filter.emitting_module {
-                let package =
-                    ObjectID::from_bytes(package.into_array()).map_err(|_| Error::InvalidFilter)?;
-                let module = Identifier::from_str(&module).map_err(|_| Error::InvalidFilter)?;
-                Ok(RpcEventFilter::MoveModule { package, module })
-            } else {
-                let package =
-                    ObjectID::from_bytes(package.into_array()).map_err(|_| Error::InvalidFilter)?;
-                Ok(RpcEventFilter::Package(package))
-            }
-        } else if let Some(event_type) = filter.event_type {
-            let event_type = StructTag::from_str(&event_type).map_err(|_| Error::InvalidFilter)?;
-            Ok(RpcEventFilter::MoveEventType(event_type))
-        } else if let Some(package) = filter.event_package {
-            if let Some(module) = filter.event_module {
-                let package =
-                    ObjectID::from_bytes(package.into_array()).map_err(|_| Error::InvalidFilter)?;
-                let module = Identifier::from_str(&module).map_err(|_| Error::InvalidFilter)?;
-                Ok(RpcEventFilter::MoveModule { package, module })
-            } else {
-                let package =
-                    ObjectID::from_bytes(package.into_array()).map_err(|_| Error::InvalidFilter)?;
-                Ok(RpcEventFilter::Package(package))
-            }
-        } else {
-            return Err(Error::InvalidFilter);
+        validate_cursor_pagination(&first, &after, &last, &before)?;
+        validate_event_filter_size(&filter, &self.limits)?;
+        validate_event_filter(&filter)?;
+
+        let backward = last.is_some();
+        let Some((mut stored_events, has_more, has_behind)) = self
+            .multi_get_events(first, after, last, before, filter, sort)
+            .await?
+        else {
+            return Ok(None);
         };
 
-        let descending_order = before.is_some();
-        let limit = self.validate_page_limit(first, last)? as usize;
-        let cursor = after
-            .or(before)
-            .map(|c| self.parse_event_cursor(c))
-            .transpose()?;
-        if let Ok(event_filter) = event_filter {
-            let results = self
-                .inner
-                .query_events_in_blocking_task(event_filter, cursor, limit, descending_order)
-                .await?;
+        // Pages are always returned in sort order, even when paginating backwards
+        if backward {
+            stored_events.reverse();
+        }
 
-            let has_next_page = results.len() > limit;
+        // Resolving the JSON representation of the events requires their type layouts
+        let results = self
+            .inner
+            .spawn_blocking(move |this| {
+                let mut layouts = EventLayoutCache::new(|type_: &StructTag| {
+                    Ok(NativeMoveObject::get_layout_from_struct_tag(
+                        type_.clone(),
+                        &this,
+                    )?)
+                });
 
-            let mut connection = Connection::new(false, has_next_page);
-            connection.edges.extend(results.into_iter().map(|e| {
-                let cursor = String::from(e.id);
-                let event = Event {
-                    sending_module_id: Some(MoveModuleId {
-                        package: SuiAddress::from_array(**e.package_id),
-                        name: e.transaction_module.to_string(),
-                    }),
-                    event_type: Some(MoveType::new(
-                        e.type_.to_canonical_string(/* with_prefix */ true),
-                    )),
-                    senders: Some(vec![Address {
-                        address: SuiAddress::from_array(e.sender.to_inner()),
-                    }]),
-                    timestamp: e.timestamp_ms.and_then(|t| DateTime::from_ms(t as i64)),
-                    json: Some(e.parsed_json.to_string()),
-                    bcs: Some(Base64::from(e.bcs)),
-                };
+                stored_events
+                    .into_iter()
+                    .map(|e| {
+                        let cursor = EventCursor::new(&e);
+                        Ok((cursor, event_from_stored(e, &mut layouts)?))
+                    })
+                    .collect::<Result<Vec<_>, IndexerError>>()
+            })
+            .await?;
 
-                Edge::new(cursor, event)
-            }));
-            Ok(Some(connection))
+        let (has_previous_page, has_next_page) = if backward {
+            (has_more, has_behind)
         } else {
-            Err(Error::InvalidFilter)
-        }
+            (has_behind, has_more)
+        };
+
+        let mut connection = Connection::new(has_previous_page, has_next_page);
+        connection.edges.extend(
+            results
+                .into_iter()
+                .map(|(cursor, event)| Edge::new(cursor.encode(), event)),
+        );
+        Ok(Some(connection))
+    }
+
+    /// Counts the events matching the filter, without fetching them. The filter is validated in
+    /// the same way as when fetching events.
+    pub(crate) async fn fetch_event_count(&self, filter: EventFilter) -> Result<u64, Error> {
+        validate_event_filter_size(&filter, &self.limits)?;
+        validate_event_filter(&filter)?;
+        Ok(self.count_events(filter).await? as u64)
+    }
+
+    /// Counts the events matching the filter per module that emitted them, ordered by package and
+    /// module name.
+    pub(crate) async fn fetch_event_counts_by_emitting_module(
+        &self,
+        filter: EventFilter,
+    ) -> Result<Vec<ModuleEventCount>, Error> {
+        validate_event_filter_size(&filter, &self.limits)?;
+        validate_event_filter(&filter)?;
+        self.count_events_by_emitting_module(filter)
+            .await?
+            .into_iter()
+            .map(|(package, name, count)| {
+                let package =
+                    SuiAddress::from_bytes(package).map_err(|e| Error::Internal(e.to_string()))?;
+                Ok(ModuleEventCount {
+                    emitting_module: MoveModuleId { package, name },
+                    count: count as u64,
+                })
+            })
+            .collect()
     }
 
     pub(crate) async fn fetch_dynamic_fields(
@@ -1803,6 +2035,87 @@ pub(crate) fn validate_cursor_pagination(
     Ok(())
 }
 
+/// Checks that the event filter is neither nested deeper nor made of more filters than the limits
+/// allow, so that it can't blow up the size of the query it translates to.
+pub(crate) fn validate_event_filter_size(
+    filter: &EventFilter,
+    limits: &Limits,
+) -> Result<(), Error> {
+    let depth = filter.depth();
+    if depth > limits.max_event_filter_depth {
+        return Err(DbValidationError::EventFilterDepthExceeded(
+            depth,
+            limits.max_event_filter_depth,
+        )
+        .into());
+    }
+
+    let nodes = filter.node_count();
+    if nodes > limits.max_event_filter_nodes {
+        return Err(DbValidationError::EventFilterNodesExceeded(
+            nodes,
+            limits.max_event_filter_nodes,
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Checks that the event filter, and each of its compound filters, constrains the events to query,
+/// that its checkpoint and time ranges are well-formed, and that it is not trivially unsatisfiable.
+pub(crate) fn validate_event_filter(filter: &EventFilter) -> Result<(), Error> {
+    if filter.is_empty() {
+        return Err(Error::InvalidFilter);
+    }
+
+    if let Some(digest) = &filter.transaction_digest {
+        TransactionDigest::from_str(digest)
+            .map_err(|_| DbValidationError::InvalidTransactionDigest)?;
+    }
+
+    if filter.emitting_module.is_some() && filter.emitting_package.is_none() {
+        return Err(DbValidationError::EmittingModuleRequiresPackage.into());
+    }
+
+    if filter.event_module.is_some() && filter.event_package.is_none() {
+        return Err(DbValidationError::EventModuleRequiresPackage.into());
+    }
+
+    if let Some(event_type) = &filter.event_type {
+        StructTag::from_str(event_type)
+            .map_err(|_| DbValidationError::InvalidEventType(event_type.clone()))?;
+    }
+
+    if let (Some(before), Some(after)) = (filter.before_checkpoint, filter.after_checkpoint) {
+        if before <= after {
+            return Err(DbValidationError::InvalidCheckpointOrder.into());
+        }
+    }
+
+    if let (Some(start_time), Some(end_time)) = (&filter.start_time, &filter.end_time) {
+        if start_time.timestamp_ms() > end_time.timestamp_ms() {
+            return Err(DbValidationError::InvalidTimeRange.into());
+        }
+    }
+
+    if filter.any.as_ref().is_some_and(|any| any.is_empty()) {
+        return Err(DbValidationError::EmptyAnyFilter.into());
+    }
+
+    if let Some(not) = &filter.not {
+        if filter.implies(not) {
+            return Err(DbValidationError::ContradictoryEventFilter.into());
+        }
+    }
+
+    for filter in filter.nested() {
+        validate_event_filter(filter)?;
+    }
+
+    Ok(())
+}
+
 pub(crate) fn convert_to_validators(
     validators: Vec<SuiValidatorSummary>,
     system_state: Option<&NativeSuiSystemStateSummary>,
@@ -1910,3 +2223,397 @@ impl From<&SuiAddress> for NativeSuiAddress {
         NativeSuiAddress::try_from(a.as_slice()).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_filter() -> EventFilter {
+        EventFilter {
+            sender: None,
+            senders: None,
+            transaction_digest: None,
+            after_checkpoint: None,
+            before_checkpoint: None,
+            emitting_package: None,
+            emitting_module: None,
+            event_package: None,
+            event_module: None,
+            event_type: None,
+            start_time: None,
+            end_time: None,
+            all: None,
+            any: None,
+            not: None,
+        }
+    }
+
+    /// A chain of `depth` filters, each nested in the `not` of the one before it.
+    fn nested_not(depth: u32) -> EventFilter {
+        (1..depth).fold(
+            EventFilter {
+                after_checkpoint: Some(1),
+                ..event_filter()
+            },
+            |filter, _| EventFilter {
+                not: Some(Box::new(filter)),
+                ..event_filter()
+            },
+        )
+    }
+
+    /// A filter with `width` alternatives in its `any`.
+    fn wide_any(width: u32) -> EventFilter {
+        EventFilter {
+            any: Some(
+                (0..width)
+                    .map(|checkpoint| EventFilter {
+                        after_checkpoint: Some(checkpoint as u64),
+                        ..event_filter()
+                    })
+                    .collect(),
+            ),
+            ..event_filter()
+        }
+    }
+
+    #[test]
+    fn test_event_filter_depth_limit() {
+        let limits = Limits {
+            max_event_filter_depth: 3,
+            max_event_filter_nodes: 100,
+            ..Default::default()
+        };
+
+        assert_eq!(nested_not(3).depth(), 3);
+        assert!(validate_event_filter_size(&nested_not(3), &limits).is_ok());
+        assert!(matches!(
+            validate_event_filter_size(&nested_not(4), &limits),
+            Err(Error::DbValidation(
+                DbValidationError::EventFilterDepthExceeded(4, 3)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_event_filter_nodes_limit() {
+        let limits = Limits {
+            max_event_filter_depth: 100,
+            max_event_filter_nodes: 5,
+            ..Default::default()
+        };
+
+        // The `any` filter itself counts as well as its alternatives
+        assert_eq!(wide_any(4).node_count(), 5);
+        assert!(validate_event_filter_size(&wide_any(4), &limits).is_ok());
+        assert!(matches!(
+            validate_event_filter_size(&wide_any(5), &limits),
+            Err(Error::DbValidation(
+                DbValidationError::EventFilterNodesExceeded(6, 5)
+            ))
+        ));
+
+        // Nested filters count towards the limit, at any depth
+        let filter = EventFilter {
+            all: Some(vec![wide_any(2), nested_not(3)]),
+            ..event_filter()
+        };
+        assert_eq!(filter.node_count(), 7);
+        assert!(validate_event_filter_size(&filter, &limits).is_err());
+    }
+
+    #[test]
+    fn test_event_filter_time_range() {
+        let start = DateTime::from_ms(1_000).unwrap();
+        let end = DateTime::from_ms(2_000).unwrap();
+
+        let filter = EventFilter {
+            start_time: Some(start.clone()),
+            end_time: Some(end.clone()),
+            ..event_filter()
+        };
+        assert!(validate_event_filter(&filter).is_ok());
+
+        let filter = EventFilter {
+            start_time: Some(start.clone()),
+            end_time: Some(start.clone()),
+            ..event_filter()
+        };
+        assert!(validate_event_filter(&filter).is_ok());
+
+        let filter = EventFilter {
+            start_time: Some(end),
+            end_time: Some(start),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::DbValidation(DbValidationError::InvalidTimeRange))
+        ));
+    }
+
+    #[test]
+    fn test_event_filter_checkpoint_range() {
+        let filter = EventFilter {
+            after_checkpoint: Some(5),
+            before_checkpoint: Some(6),
+            ..event_filter()
+        };
+        assert!(validate_event_filter(&filter).is_ok());
+
+        let filter = EventFilter {
+            after_checkpoint: Some(5),
+            before_checkpoint: Some(5),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::DbValidation(
+                DbValidationError::InvalidCheckpointOrder
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_compound_event_filters() {
+        let sender = |address: &str| EventFilter {
+            sender: Some(SuiAddress::from_str(address).unwrap()),
+            ..event_filter()
+        };
+
+        let filter = EventFilter {
+            any: Some(vec![
+                sender("0x1"),
+                EventFilter {
+                    all: Some(vec![
+                        sender("0x2"),
+                        EventFilter {
+                            not: Some(Box::new(sender("0x3"))),
+                            ..event_filter()
+                        },
+                    ]),
+                    ..event_filter()
+                },
+            ]),
+            ..event_filter()
+        };
+        assert!(validate_event_filter(&filter).is_ok());
+
+        // Nested filters are validated too
+        let filter = EventFilter {
+            all: Some(vec![sender("0x1"), event_filter()]),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::InvalidFilter)
+        ));
+
+        let filter = EventFilter {
+            any: Some(vec![]),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::DbValidation(DbValidationError::EmptyAnyFilter))
+        ));
+    }
+
+    #[test]
+    fn test_contradictory_event_filter() {
+        let address = SuiAddress::from_str("0x1").unwrap();
+        let filter = EventFilter {
+            sender: Some(address),
+            after_checkpoint: Some(5),
+            not: Some(Box::new(EventFilter {
+                sender: Some(address),
+                ..event_filter()
+            })),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::DbValidation(
+                DbValidationError::ContradictoryEventFilter
+            ))
+        ));
+
+        // Excluding a narrower set of events is fine
+        let filter = EventFilter {
+            sender: Some(address),
+            not: Some(Box::new(EventFilter {
+                sender: Some(address),
+                after_checkpoint: Some(5),
+                ..event_filter()
+            })),
+            ..event_filter()
+        };
+        assert!(validate_event_filter(&filter).is_ok());
+    }
+
+    #[test]
+    fn test_event_filter_transaction_digest() {
+        let digest = |digest: &str| EventFilter {
+            transaction_digest: Some(digest.to_string()),
+            ..event_filter()
+        };
+
+        let valid = TransactionDigest::new([7; 32]).base58_encode();
+        assert!(validate_event_filter(&digest(&valid)).is_ok());
+
+        // Too short
+        assert!(matches!(
+            validate_event_filter(&digest(&valid[..valid.len() / 2])),
+            Err(Error::DbValidation(
+                DbValidationError::InvalidTransactionDigest
+            ))
+        ));
+
+        // '0', 'O', 'I' and 'l' are not part of the base58 alphabet
+        let err = validate_event_filter(&digest("0OIl")).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DbValidation(DbValidationError::InvalidTransactionDigest)
+        ));
+        assert_eq!(err.to_string(), "invalid transaction digest");
+    }
+
+    #[test]
+    fn test_event_cursor() {
+        let cursor = EventCursor {
+            timestamp_ms: 1_000,
+            checkpoint_sequence_number: 10,
+            tx_sequence_number: 1234,
+            event_sequence_number: 2,
+        };
+        assert_eq!(EventCursor::decode(&cursor.encode()).unwrap(), cursor);
+
+        assert!(matches!(
+            EventCursor::decode("not a cursor"),
+            Err(Error::InvalidCursor(_))
+        ));
+        // A transaction digest and event sequence number, as events were once paginated by
+        assert!(matches!(
+            EventCursor::decode("8dNgFnnmcF5uJRwTnB9rYf4Ek2JANnNqhqtrrQbfKd6o:0"),
+            Err(Error::InvalidCursor(_))
+        ));
+    }
+
+    #[test]
+    fn test_event_layout_cache() {
+        let mut lookups = 0;
+        let mut layouts = EventLayoutCache::new(|type_: &StructTag| {
+            lookups += 1;
+            Ok(MoveStructLayout {
+                type_: type_.clone(),
+                fields: vec![],
+            })
+        });
+
+        // A page of events of the same type
+        let created = StructTag::from_str("0x2::coin::CurrencyCreated<0x2::sui::SUI>").unwrap();
+        for _ in 0..10 {
+            assert_eq!(layouts.layout(&created).unwrap().type_, created);
+        }
+
+        let other = StructTag::from_str("0x2::coin::CurrencyCreated<0x3::foo::FOO>").unwrap();
+        assert_eq!(layouts.layout(&other).unwrap().type_, other);
+
+        drop(layouts);
+        assert_eq!(lookups, 2);
+    }
+
+    #[test]
+    fn test_event_from_stored_without_contents() {
+        let stored = StoredEvent {
+            tx_sequence_number: 1234,
+            event_sequence_number: 2,
+            transaction_digest: TransactionDigest::default().inner().to_vec(),
+            checkpoint_sequence_number: 10,
+            senders: vec![Some(vec![0x12, 0x34])],
+            package: vec![2; 32],
+            module: "coin".to_string(),
+            event_type: "0x2::coin::CurrencyCreated<0x2::sui::SUI>".to_string(),
+            timestamp_ms: 1_000,
+            bcs: vec![],
+        };
+
+        // There are no contents to resolve the layout of
+        let mut layouts = EventLayoutCache::new(|_: &StructTag| -> Result<_, IndexerError> {
+            panic!("Resolved the layout of an event without contents")
+        });
+        let event = event_from_stored(stored, &mut layouts).unwrap();
+        assert_eq!(event.bcs, None);
+        assert_eq!(event.json, None);
+        assert_eq!(
+            event.sender_addresses,
+            Some(vec![SuiAddress::from_str("0x1234").unwrap()])
+        );
+        assert_eq!(event.sequence_number, Some(2));
+    }
+
+    #[test]
+    fn test_event_filter_event_type() {
+        let event_type = |event_type: &str| EventFilter {
+            event_type: Some(event_type.to_string()),
+            ..event_filter()
+        };
+
+        assert!(validate_event_filter(&event_type("0x2::coin::CurrencyCreated")).is_ok());
+        assert!(
+            validate_event_filter(&event_type("0x2::coin::CurrencyCreated<0x2::sui::SUI>")).is_ok()
+        );
+
+        // Missing the type's name
+        assert!(matches!(
+            validate_event_filter(&event_type("0x2::coin")),
+            Err(Error::DbValidation(DbValidationError::InvalidEventType(_)))
+        ));
+    }
+
+    #[test]
+    fn test_event_filter_cascading_modules() {
+        let package = SuiAddress::from_str("0x2").unwrap();
+
+        let filter = EventFilter {
+            emitting_package: Some(package),
+            emitting_module: Some("coin".to_string()),
+            event_package: Some(package),
+            event_module: Some("coin".to_string()),
+            ..event_filter()
+        };
+        assert!(validate_event_filter(&filter).is_ok());
+
+        let filter = EventFilter {
+            sender: Some(package),
+            emitting_module: Some("coin".to_string()),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::DbValidation(
+                DbValidationError::EmittingModuleRequiresPackage
+            ))
+        ));
+
+        let filter = EventFilter {
+            emitting_package: Some(package),
+            event_module: Some("coin".to_string()),
+            ..event_filter()
+        };
+        assert!(matches!(
+            validate_event_filter(&filter),
+            Err(Error::DbValidation(
+                DbValidationError::EventModuleRequiresPackage
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_empty_event_filter() {
+        assert!(matches!(
+            validate_event_filter(&event_filter()),
+            Err(Error::InvalidFilter)
+        ));
+    }
+}
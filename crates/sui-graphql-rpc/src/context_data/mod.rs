@@ -1,6 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod address_loader;
 pub(crate) mod db_backend;
 pub(crate) mod db_data_provider;
 pub(crate) mod package_cache;
@@ -2,28 +2,40 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{
-    db_backend::{BalanceQuery, Explain, Explained, GenericQueryBuilder},
+    db_backend::{
+        BalanceQuery, EventCountQuery, Explain, Explained, GenericQueryBuilder,
+        ModuleEventCountQuery,
+    },
     db_data_provider::DbValidationError,
 };
 use crate::context_data::db_data_provider::PgManager;
 use crate::{
     error::Error,
-    types::{digest::Digest, object::ObjectFilter, transaction_block::TransactionBlockFilter},
+    types::{
+        digest::Digest, event::EventFilter, object::ObjectFilter, sui_address::SuiAddress,
+        transaction_block::TransactionBlockFilter,
+    },
 };
 use async_trait::async_trait;
 use diesel::{
+    dsl::{count_star, not, sql},
+    expression::BoxableExpression,
     pg::Pg,
     query_builder::{AstPass, QueryFragment},
+    sql_types::Bool,
     BoolExpressionMethods, ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+    TextExpressionMethods,
 };
+use move_core_types::language_storage::StructTag;
 use std::str::FromStr;
 use sui_indexer::{
     schema_v2::{
-        checkpoints, epochs, objects, transactions, tx_calls, tx_changed_objects, tx_input_objects,
-        tx_recipients, tx_senders,
+        checkpoints, epochs, events, objects, transactions, tx_calls, tx_changed_objects,
+        tx_input_objects, tx_recipients, tx_senders,
     },
     types_v2::OwnerType,
 };
+use sui_types::digests::TransactionDigest;
 
 pub(crate) struct PgQueryBuilder;
 
@@ -362,6 +374,218 @@ impl GenericQueryBuilder<Pg> for PgQueryBuilder {
 
         query
     }
+    fn multi_get_events(
+        cursor: Option<(i64, i64, i64, i64)>,
+        descending_order: bool,
+        limit: i64,
+        filter: EventFilter,
+    ) -> Result<events::BoxedQuery<'static, Pg>, Error> {
+        let mut query = events::dsl::events.into_boxed();
+
+        // Events are ordered by timestamp, then by checkpoint, then by transaction, then by their
+        // position in the transaction
+        if let Some((timestamp_ms, checkpoint, tx_seq, event_seq)) = cursor {
+            if descending_order {
+                query = query.filter(
+                    events::dsl::timestamp_ms
+                        .lt(timestamp_ms)
+                        .or(events::dsl::timestamp_ms.eq(timestamp_ms).and(
+                            events::dsl::checkpoint_sequence_number.lt(checkpoint).or(
+                                events::dsl::checkpoint_sequence_number.eq(checkpoint).and(
+                                    events::dsl::tx_sequence_number.lt(tx_seq).or(
+                                        events::dsl::tx_sequence_number
+                                            .eq(tx_seq)
+                                            .and(events::dsl::event_sequence_number.lt(event_seq)),
+                                    ),
+                                ),
+                            ),
+                        )),
+                );
+            } else {
+                query = query.filter(
+                    events::dsl::timestamp_ms
+                        .gt(timestamp_ms)
+                        .or(events::dsl::timestamp_ms.eq(timestamp_ms).and(
+                            events::dsl::checkpoint_sequence_number.gt(checkpoint).or(
+                                events::dsl::checkpoint_sequence_number.eq(checkpoint).and(
+                                    events::dsl::tx_sequence_number.gt(tx_seq).or(
+                                        events::dsl::tx_sequence_number
+                                            .eq(tx_seq)
+                                            .and(events::dsl::event_sequence_number.gt(event_seq)),
+                                    ),
+                                ),
+                            ),
+                        )),
+                );
+            }
+        }
+        if descending_order {
+            query = query.order((
+                events::dsl::timestamp_ms.desc(),
+                events::dsl::checkpoint_sequence_number.desc(),
+                events::dsl::tx_sequence_number.desc(),
+                events::dsl::event_sequence_number.desc(),
+            ));
+        } else {
+            query = query.order((
+                events::dsl::timestamp_ms.asc(),
+                events::dsl::checkpoint_sequence_number.asc(),
+                events::dsl::tx_sequence_number.asc(),
+                events::dsl::event_sequence_number.asc(),
+            ));
+        }
+        query = query.limit(limit + 1);
+
+        if let Some(condition) = event_condition(filter)? {
+            query = query.filter(condition);
+        }
+
+        Ok(query)
+    }
+    fn count_events(filter: EventFilter) -> Result<EventCountQuery<'static, Pg>, Error> {
+        let mut query = events::dsl::events.select(count_star()).into_boxed();
+
+        if let Some(condition) = event_condition(filter)? {
+            query = query.filter(condition);
+        }
+
+        Ok(query)
+    }
+    fn count_events_by_emitting_module(
+        filter: EventFilter,
+    ) -> Result<ModuleEventCountQuery<'static, Pg>, Error> {
+        let mut query = events::dsl::events
+            .group_by((events::dsl::package, events::dsl::module))
+            .select((events::dsl::package, events::dsl::module, count_star()))
+            .order((events::dsl::package.asc(), events::dsl::module.asc()))
+            .into_boxed();
+
+        if let Some(condition) = event_condition(filter)? {
+            query = query.filter(condition);
+        }
+
+        Ok(query)
+    }
+}
+
+/// A condition on the rows of the events table.
+type EventCondition = Box<dyn BoxableExpression<events::table, Pg, SqlType = Bool> + Send>;
+
+/// Translates an event filter into the condition that events must satisfy to match it, or `None`
+/// if the filter places no constraint on events. All criteria of the filter must be satisfied,
+/// including its compound filters: each of `all`, at least one of `any`, and not `not`.
+fn event_condition(filter: EventFilter) -> Result<Option<EventCondition>, Error> {
+    let mut conditions: Vec<EventCondition> = vec![];
+
+    if let Some(sender) = filter.sender {
+        let subquery = tx_senders::dsl::tx_senders
+            .filter(tx_senders::dsl::sender.eq(sender.into_vec()))
+            .select(tx_senders::dsl::tx_sequence_number);
+
+        conditions.push(Box::new(events::dsl::tx_sequence_number.eq_any(subquery)));
+    }
+    // Applies on top of `sender`, if both are set
+    if let Some(senders) = filter.senders {
+        let senders: Vec<_> = senders.into_iter().map(SuiAddress::into_vec).collect();
+        let subquery = tx_senders::dsl::tx_senders
+            .filter(tx_senders::dsl::sender.eq_any(senders))
+            .select(tx_senders::dsl::tx_sequence_number);
+
+        conditions.push(Box::new(events::dsl::tx_sequence_number.eq_any(subquery)));
+    }
+    if let Some(digest) = filter.transaction_digest {
+        let digest = TransactionDigest::from_str(&digest)
+            .map_err(|_| DbValidationError::InvalidTransactionDigest)?;
+        conditions.push(Box::new(
+            events::dsl::transaction_digest.eq(digest.into_inner().to_vec()),
+        ));
+    }
+    if let Some(checkpoint) = filter.after_checkpoint {
+        conditions.push(Box::new(
+            events::dsl::checkpoint_sequence_number.gt(checkpoint as i64),
+        ));
+    }
+    if let Some(checkpoint) = filter.before_checkpoint {
+        conditions.push(Box::new(
+            events::dsl::checkpoint_sequence_number.lt(checkpoint as i64),
+        ));
+    }
+
+    // Cascading: the module is only considered along with its package
+    if let Some(package) = filter.emitting_package {
+        conditions.push(Box::new(events::dsl::package.eq(package.into_vec())));
+        if let Some(module) = filter.emitting_module {
+            conditions.push(Box::new(events::dsl::module.eq(module)));
+        }
+    }
+    if let Some(package) = filter.event_package {
+        // Event types are stored in their canonical form, with the full package address
+        let prefix = match filter.event_module {
+            Some(module) => format!("{package}::{}::%", escape_like(&module)),
+            None => format!("{package}::%"),
+        };
+        conditions.push(Box::new(events::dsl::event_type.like(prefix)));
+    }
+    if let Some(event_type) = filter.event_type {
+        let event_type = StructTag::from_str(&event_type)
+            .map_err(|_| DbValidationError::InvalidEventType(event_type))?;
+        let canonical = event_type.to_canonical_string(/* with_prefix */ true);
+        if event_type.type_params.is_empty() {
+            // Without type arguments, the type matches any of its instantiations
+            let instantiations = format!("{}<%", escape_like(&canonical));
+            conditions.push(Box::new(
+                events::dsl::event_type
+                    .eq(canonical)
+                    .or(events::dsl::event_type.like(instantiations)),
+            ));
+        } else {
+            conditions.push(Box::new(events::dsl::event_type.eq(canonical)));
+        }
+    }
+
+    if let Some(start_time) = filter.start_time {
+        conditions.push(Box::new(
+            events::dsl::timestamp_ms.ge(start_time.timestamp_ms()),
+        ));
+    }
+    if let Some(end_time) = filter.end_time {
+        conditions.push(Box::new(
+            events::dsl::timestamp_ms.lt(end_time.timestamp_ms()),
+        ));
+    }
+
+    for filter in filter.all.into_iter().flatten() {
+        conditions.extend(event_condition(filter)?);
+    }
+    if let Some(filters) = filter.any {
+        let alternatives = filters
+            .into_iter()
+            .map(event_condition)
+            .collect::<Result<Vec<_>, _>>()?;
+        // An alternative that places no constraint matches every event, and so does `any`
+        if alternatives.iter().all(Option::is_some) {
+            let condition = alternatives
+                .into_iter()
+                .flatten()
+                .reduce(|a, b| -> EventCondition { Box::new(a.or(b)) });
+            conditions.push(condition.unwrap_or_else(|| Box::new(sql::<Bool>("FALSE"))));
+        }
+    }
+    if let Some(filter) = filter.not {
+        let condition = event_condition(*filter)?.unwrap_or_else(|| Box::new(sql::<Bool>("TRUE")));
+        conditions.push(Box::new(not(condition)));
+    }
+
+    Ok(conditions
+        .into_iter()
+        .reduce(|a, b| -> EventCondition { Box::new(a.and(b)) }))
+}
+
+/// Escapes the characters that have a special meaning in a LIKE pattern.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
 /// Allows methods like load(), get_result(), etc. on an Explained query
@@ -486,6 +710,254 @@ pub(crate) type QueryBuilder = PgQueryBuilder;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::date_time::DateTime;
+    use diesel::debug_query;
+
+    fn event_filter() -> EventFilter {
+        EventFilter {
+            sender: None,
+            senders: None,
+            transaction_digest: None,
+            after_checkpoint: None,
+            before_checkpoint: None,
+            emitting_package: None,
+            emitting_module: None,
+            event_package: None,
+            event_module: None,
+            event_type: None,
+            start_time: None,
+            end_time: None,
+            all: None,
+            any: None,
+            not: None,
+        }
+    }
+
+    fn events_sql(filter: EventFilter) -> String {
+        let query = PgQueryBuilder::multi_get_events(None, false, 10, filter).unwrap();
+        debug_query::<Pg, _>(&query).to_string()
+    }
+
+    #[test]
+    fn test_events_time_range() {
+        let sql = events_sql(EventFilter {
+            start_time: Some(DateTime::from_ms(1_000).unwrap()),
+            end_time: Some(DateTime::from_ms(2_000).unwrap()),
+            ..event_filter()
+        });
+        assert!(sql.contains(r#""events"."timestamp_ms" >= $"#));
+        assert!(sql.contains(r#""events"."timestamp_ms" < $"#));
+        assert!(sql.ends_with("binds: [1000, 2000, 11]"));
+    }
+
+    #[test]
+    fn test_events_time_range_composes() {
+        let sql = events_sql(EventFilter {
+            sender: Some(SuiAddress::from_str("0x42").unwrap()),
+            start_time: Some(DateTime::from_ms(1_000).unwrap()),
+            ..event_filter()
+        });
+        assert!(sql.contains(r#""tx_senders"."sender" = $"#));
+        assert!(sql.contains(r#""events"."timestamp_ms" >= $"#));
+        assert!(!sql.contains(r#""events"."timestamp_ms" < $"#));
+    }
+
+    fn sender(address: &str) -> EventFilter {
+        EventFilter {
+            sender: Some(SuiAddress::from_str(address).unwrap()),
+            ..event_filter()
+        }
+    }
+
+    fn after_checkpoint(checkpoint: u64) -> EventFilter {
+        EventFilter {
+            after_checkpoint: Some(checkpoint),
+            ..event_filter()
+        }
+    }
+
+    #[test]
+    fn test_events_senders() {
+        let sql = events_sql(EventFilter {
+            senders: Some(vec![
+                SuiAddress::from_str("0x1").unwrap(),
+                SuiAddress::from_str("0x2").unwrap(),
+                SuiAddress::from_str("0x3").unwrap(),
+            ]),
+            ..event_filter()
+        });
+        assert_eq!(sql.matches(r#""tx_senders"."sender" = ANY($"#).count(), 1);
+
+        // Both sets of senders must be satisfied
+        let sql = events_sql(EventFilter {
+            senders: Some(vec![
+                SuiAddress::from_str("0x1").unwrap(),
+                SuiAddress::from_str("0x2").unwrap(),
+            ]),
+            ..sender("0x1")
+        });
+        assert!(sql.contains(r#""tx_senders"."sender" = $"#));
+        assert!(sql.contains(r#""tx_senders"."sender" = ANY($"#));
+        assert!(sql.contains(" AND "));
+    }
+
+    #[test]
+    fn test_events_unparameterized_type() {
+        let sql = events_sql(EventFilter {
+            event_type: Some("0x2::coin::CurrencyCreated".to_string()),
+            ..event_filter()
+        });
+        assert!(
+            sql.contains(r#"(("events"."event_type" = $1) OR ("events"."event_type" LIKE $2))"#)
+        );
+        let canonical = format!("0x{}::coin::CurrencyCreated", "0".repeat(63) + "2");
+        assert!(sql.contains(&format!(r#""{canonical}", "{canonical}<%""#)));
+    }
+
+    #[test]
+    fn test_events_instantiated_type() {
+        let sql = events_sql(EventFilter {
+            event_type: Some("0x2::coin::CurrencyCreated<0x2::sui::SUI>".to_string()),
+            ..event_filter()
+        });
+        assert!(sql.contains(r#""events"."event_type" = $1"#));
+        assert!(!sql.contains("LIKE"));
+        assert!(sql.contains("::sui::SUI>"));
+    }
+
+    #[test]
+    fn test_events_all() {
+        let sql = events_sql(EventFilter {
+            all: Some(vec![after_checkpoint(5), after_checkpoint(6)]),
+            ..event_filter()
+        });
+        assert!(sql.contains(
+            r#"(("events"."checkpoint_sequence_number" > $1) AND ("events"."checkpoint_sequence_number" > $2))"#
+        ));
+    }
+
+    #[test]
+    fn test_events_any() {
+        let sql = events_sql(EventFilter {
+            any: Some(vec![after_checkpoint(5), after_checkpoint(6)]),
+            ..event_filter()
+        });
+        assert!(sql.contains(
+            r#"(("events"."checkpoint_sequence_number" > $1) OR ("events"."checkpoint_sequence_number" > $2))"#
+        ));
+    }
+
+    #[test]
+    fn test_events_not() {
+        let sql = events_sql(EventFilter {
+            not: Some(Box::new(after_checkpoint(5))),
+            ..event_filter()
+        });
+        assert!(sql.contains(r#"NOT ("events"."checkpoint_sequence_number" > $1)"#));
+    }
+
+    #[test]
+    fn test_events_nested_compound_filters() {
+        // after 5, and either from 0x1 or (from 0x2 but not after 8)
+        let sql = events_sql(EventFilter {
+            after_checkpoint: Some(5),
+            any: Some(vec![
+                sender("0x1"),
+                EventFilter {
+                    all: Some(vec![
+                        sender("0x2"),
+                        EventFilter {
+                            not: Some(Box::new(after_checkpoint(8))),
+                            ..event_filter()
+                        },
+                    ]),
+                    ..event_filter()
+                },
+            ]),
+            ..event_filter()
+        });
+        assert_eq!(sql.matches(r#""tx_senders"."sender" = $"#).count(), 2);
+        assert!(sql.contains(" OR "));
+        assert!(sql.contains(r#"NOT ("events"."checkpoint_sequence_number" > $"#));
+    }
+
+    #[test]
+    fn test_events_ascending_order() {
+        let query =
+            PgQueryBuilder::multi_get_events(Some((1_000, 5, 7, 2)), false, 10, sender("0x1"))
+                .unwrap();
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"ORDER BY "events"."timestamp_ms" ASC, "events"."checkpoint_sequence_number" ASC, "events"."tx_sequence_number" ASC, "events"."event_sequence_number" ASC"#
+        ));
+        // Events with the same timestamp as the cursor are paginated on their checkpoint and
+        // sequence numbers
+        assert!(sql.contains(
+            r#"(("events"."timestamp_ms" > $1) OR (("events"."timestamp_ms" = $2) AND (("events"."checkpoint_sequence_number" > $3) OR (("events"."checkpoint_sequence_number" = $4) AND (("events"."tx_sequence_number" > $5) OR (("events"."tx_sequence_number" = $6) AND ("events"."event_sequence_number" > $7)))))))"#
+        ));
+    }
+
+    #[test]
+    fn test_events_descending_order() {
+        let query =
+            PgQueryBuilder::multi_get_events(Some((1_000, 5, 7, 2)), true, 10, sender("0x1"))
+                .unwrap();
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(
+            r#"ORDER BY "events"."timestamp_ms" DESC, "events"."checkpoint_sequence_number" DESC, "events"."tx_sequence_number" DESC, "events"."event_sequence_number" DESC"#
+        ));
+        // Events with the same timestamp as the cursor are paginated on their checkpoint and
+        // sequence numbers
+        assert!(sql.contains(
+            r#"(("events"."timestamp_ms" < $1) OR (("events"."timestamp_ms" = $2) AND (("events"."checkpoint_sequence_number" < $3) OR (("events"."checkpoint_sequence_number" = $4) AND (("events"."tx_sequence_number" < $5) OR (("events"."tx_sequence_number" = $6) AND ("events"."event_sequence_number" < $7)))))))"#
+        ));
+    }
+
+    #[test]
+    fn test_count_events() {
+        let query = PgQueryBuilder::count_events(sender("0x1")).unwrap();
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.starts_with("SELECT COUNT(*) FROM"));
+        assert!(sql.contains(r#""tx_senders"."sender" = $"#));
+        assert!(!sql.contains("ORDER BY"));
+        assert!(!sql.contains("LIMIT"));
+    }
+
+    #[test]
+    fn test_count_events_by_emitting_module() {
+        let query = PgQueryBuilder::count_events_by_emitting_module(sender("0x1")).unwrap();
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#"COUNT(*)"#));
+        assert!(sql.contains(r#""tx_senders"."sender" = $"#));
+        assert!(sql.contains(r#"GROUP BY "events"."package", "events"."module""#));
+    }
+
+    #[test]
+    fn test_events_checkpoint_range() {
+        let sql = events_sql(EventFilter {
+            after_checkpoint: Some(5),
+            before_checkpoint: Some(8),
+            ..event_filter()
+        });
+        assert!(sql.contains(r#""events"."checkpoint_sequence_number" > $"#));
+        assert!(sql.contains(r#""events"."checkpoint_sequence_number" < $"#));
+        assert!(sql.ends_with("binds: [5, 8, 11]"));
+    }
+
+    #[test]
+    fn test_events_checkpoint_range_composes() {
+        let digest = TransactionDigest::random();
+        let sql = events_sql(EventFilter {
+            sender: Some(SuiAddress::from_str("0x42").unwrap()),
+            transaction_digest: Some(digest.to_string()),
+            after_checkpoint: Some(5),
+            ..event_filter()
+        });
+        assert!(sql.contains(r#""tx_senders"."sender" = $"#));
+        assert!(sql.contains(r#""events"."transaction_digest" = $"#));
+        assert!(sql.contains(r#""events"."checkpoint_sequence_number" > $"#));
+        assert!(!sql.contains(r#""events"."checkpoint_sequence_number" < $"#));
+    }
 
     #[test]
     fn test_invalid_json() {
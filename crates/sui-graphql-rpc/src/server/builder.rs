@@ -1,7 +1,7 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::context_data::package_cache::DbPackageStore;
+use crate::context_data::{address_loader::AddressLoader, package_cache::DbPackageStore};
 use crate::{
     config::ServerConfig,
     context_data::db_data_provider::PgManager,
@@ -16,7 +16,7 @@ use crate::{
     server::version::{check_version_middleware, set_version_middleware},
     types::query::{Query, SuiGraphQLSchema},
 };
-use async_graphql::{extensions::ExtensionFactory, Schema, SchemaBuilder};
+use async_graphql::{dataloader::DataLoader, extensions::ExtensionFactory, Schema, SchemaBuilder};
 use async_graphql::{EmptyMutation, EmptySubscription};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use axum::http::HeaderMap;
@@ -85,6 +85,7 @@ impl Server {
             .context_data(config.service.clone())
             .context_data(pg_conn_pool)
             .context_data(Resolver::new(package_cache))
+            .context_data(DataLoader::new(AddressLoader::default(), tokio::spawn))
             .context_data(name_service_config)
             .ide_title(config.ide.ide_title.clone())
             .context_data(Arc::new(metrics))
@@ -260,15 +261,21 @@ pub mod tests {
         extensions::timeout::Timeout,
         metrics::RequestMetrics,
         test_infra::cluster::{serve_executor, ExecutorCluster, DEFAULT_INTERNAL_DATA_SOURCE_PORT},
+        types::move_module::MoveModuleId,
     };
     use async_graphql::{
         extensions::{Extension, ExtensionContext, NextExecute},
-        Response,
+        EmptyMutation, EmptySubscription, Response, Schema,
     };
     use rand::{rngs::StdRng, SeedableRng};
     use simulacrum::Simulacrum;
+    use std::collections::HashSet;
     use std::sync::Arc;
     use std::time::Duration;
+    use sui_test_transaction_builder::TestTransactionBuilder;
+    use sui_types::{
+        base_types::SuiAddress, sui_system_state::SuiSystemStateTrait, SUI_FRAMEWORK_ADDRESS,
+    };
 
     async fn prep_cluster() -> (ConnectionConfig, ExecutorCluster) {
         let rng = StdRng::from_seed([12; 32]);
@@ -586,4 +593,314 @@ pub mod tests {
         assert_eq!(metrics2.num_nodes.get_sample_sum(), 2. + 4.);
         assert_eq!(metrics2.query_depth.get_sample_sum(), 1. + 3.);
     }
+
+    pub async fn test_move_module_id_module_impl() {
+        let (connection_config, _cluster) = prep_cluster().await;
+
+        async fn exec_module_id(name: &str, connection_config: &ConnectionConfig) -> Response {
+            let db_url: String = connection_config.db_url.clone();
+            let reader = PgManager::reader(db_url).expect("Failed to create pg connection pool");
+            let pg_conn_pool = PgManager::new(reader, Limits::default());
+            let module_id = MoveModuleId {
+                package: SUI_FRAMEWORK_ADDRESS.into(),
+                name: name.to_string(),
+            };
+
+            Schema::build(module_id, EmptyMutation, EmptySubscription)
+                .data(pg_conn_pool)
+                .finish()
+                .execute("{ module { moduleId { name } } }")
+                .await
+        }
+
+        // A module that exists in the framework
+        let resp = exec_module_id("coin", &connection_config).await;
+        assert!(resp.is_ok());
+        assert_eq!(
+            resp.data.into_json().unwrap(),
+            serde_json::json!({ "module": { "moduleId": { "name": "coin" } } }),
+        );
+
+        // A module that does not exist
+        let resp = exec_module_id("not_a_module", &connection_config).await;
+        assert!(resp.is_ok());
+        assert_eq!(
+            resp.data.into_json().unwrap(),
+            serde_json::json!({ "module": null }),
+        );
+    }
+
+    /// Serves a cluster whose events are spread over several checkpoints, a second apart: three
+    /// accounts stake in checkpoints of their own (1, 2 and 3), then the first two stake again in
+    /// the same checkpoint (4), so that their events share a timestamp. Returns the accounts, in
+    /// the order they first staked.
+    async fn prep_events_cluster() -> (ExecutorCluster, Vec<SuiAddress>) {
+        let rng = StdRng::from_seed([12; 32]);
+        let mut sim = Simulacrum::new_with_rng(rng);
+
+        let validator = sim
+            .store()
+            .get_system_state()
+            .into_sui_system_state_summary()
+            .active_validators[0]
+            .sui_address;
+        let gas_price = sim.reference_gas_price();
+
+        // Every staking transaction pays with a gas coin of its own, and stakes another coin
+        let mut senders = vec![];
+        let mut stakes = vec![];
+        for (sender, key) in sim.keystore().accounts().take(3) {
+            let mut coins = sim
+                .store()
+                .owned_objects(*sender)
+                .filter(|object| object.is_gas_coin())
+                .map(|object| object.compute_object_reference());
+            let mut stake = || {
+                let (gas, coin) = (coins.next().unwrap(), coins.next().unwrap());
+                TestTransactionBuilder::new(*sender, gas, gas_price)
+                    .call_staking(coin, validator)
+                    .build_and_sign(key)
+            };
+            senders.push(*sender);
+            stakes.push((stake(), stake()));
+        }
+
+        let (first_stakes, second_stakes): (Vec<_>, Vec<_>) = stakes.into_iter().unzip();
+        for tx in first_stakes {
+            sim.advance_clock(Duration::from_secs(1));
+            let (_, error) = sim.execute_transaction(tx).unwrap();
+            assert!(error.is_none(), "{:?}", error);
+            sim.create_checkpoint();
+        }
+        sim.advance_clock(Duration::from_secs(1));
+        for tx in second_stakes.into_iter().take(2) {
+            let (_, error) = sim.execute_transaction(tx).unwrap();
+            assert!(error.is_none(), "{:?}", error);
+        }
+        sim.create_checkpoint();
+
+        let cluster = serve_executor(
+            ConnectionConfig::ci_integration_test_cfg(),
+            DEFAULT_INTERNAL_DATA_SOURCE_PORT,
+            Arc::new(sim),
+        )
+        .await;
+        cluster
+            .wait_for_checkpoint_catchup(4, Duration::from_secs(10))
+            .await;
+
+        (cluster, senders)
+    }
+
+    /// The transaction digest, checkpoint, timestamp and senders of the events returned by an
+    /// `eventConnection` query with the given arguments.
+    async fn query_events(cluster: &ExecutorCluster, args: &str) -> Vec<serde_json::Value> {
+        let query = format!(
+            "{{ eventConnection(first: 50, {args}) {{ nodes {{
+                transactionDigest
+                timestamp
+                senders {{ location }}
+                transaction {{ effects {{ checkpoint {{ sequenceNumber }} }} }}
+            }} }} }}"
+        );
+        let resp = cluster.graphql_client.execute(query, vec![]).await.unwrap();
+        assert!(resp.get("errors").is_none(), "{}", resp);
+        resp["data"]["eventConnection"]["nodes"]
+            .as_array()
+            .unwrap()
+            .clone()
+    }
+
+    fn event_checkpoint(event: &serde_json::Value) -> u64 {
+        event["transaction"]["effects"]["checkpoint"]["sequenceNumber"]
+            .as_u64()
+            .unwrap()
+    }
+
+    pub async fn test_event_checkpoint_range_impl() {
+        let (cluster, _senders) = prep_events_cluster().await;
+
+        let all = query_events(&cluster, "filter: { afterCheckpoint: 0 }").await;
+        let checkpoints: Vec<_> = all.iter().map(event_checkpoint).collect();
+        for checkpoint in 1..=4 {
+            assert!(checkpoints.contains(&checkpoint));
+        }
+
+        // Both bounds are exclusive
+        let in_range = |after: u64, before: u64| -> Vec<serde_json::Value> {
+            all.iter()
+                .filter(|event| (after + 1..before).contains(&event_checkpoint(event)))
+                .cloned()
+                .collect()
+        };
+        let resp = query_events(
+            &cluster,
+            "filter: { afterCheckpoint: 1, beforeCheckpoint: 3 }",
+        )
+        .await;
+        assert!(!resp.is_empty());
+        assert_eq!(resp, in_range(1, 3));
+
+        let resp = query_events(&cluster, "filter: { afterCheckpoint: 3 }").await;
+        assert_eq!(resp, in_range(3, u64::MAX));
+
+        // No checkpoint lies strictly between two consecutive ones
+        let resp = query_events(
+            &cluster,
+            "filter: { afterCheckpoint: 2, beforeCheckpoint: 3 }",
+        )
+        .await;
+        assert!(resp.is_empty());
+    }
+
+    fn event_senders(event: &serde_json::Value) -> Vec<String> {
+        event["senders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|sender| sender["location"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    pub async fn test_event_senders_impl() {
+        let (cluster, senders) = prep_events_cluster().await;
+        let senders: Vec<_> = senders.iter().map(SuiAddress::to_string).collect();
+        let senders_filter = |senders: &[&String]| {
+            let senders: Vec<_> = senders.iter().map(|s| format!("\"{s}\"")).collect();
+            format!(
+                "filter: {{ afterCheckpoint: 0, senders: [{}] }}",
+                senders.join(", ")
+            )
+        };
+
+        let all = query_events(&cluster, "filter: { afterCheckpoint: 0 }").await;
+        let sent_by_any = |senders: &[&String]| -> Vec<serde_json::Value> {
+            all.iter()
+                .filter(|event| {
+                    event_senders(event)
+                        .iter()
+                        .any(|sender| senders.contains(&sender))
+                })
+                .cloned()
+                .collect()
+        };
+
+        // Each account emitted events, the first two of them in two transactions
+        for (sender, transactions) in senders.iter().zip([2, 2, 1]) {
+            let resp = query_events(&cluster, &senders_filter(&[sender])).await;
+            let digests: HashSet<_> = resp
+                .iter()
+                .map(|event| event["transactionDigest"].as_str().unwrap())
+                .collect();
+            assert_eq!(digests.len(), transactions);
+            assert_eq!(resp, sent_by_any(&[sender]));
+        }
+
+        let resp = query_events(&cluster, &senders_filter(&[&senders[0], &senders[2]])).await;
+        assert_eq!(resp, sent_by_any(&[&senders[0], &senders[2]]));
+        assert!(resp
+            .iter()
+            .all(|event| !event_senders(event).contains(&senders[1])));
+
+        let all_senders: Vec<_> = senders.iter().collect();
+        let resp = query_events(&cluster, &senders_filter(&all_senders)).await;
+        assert_eq!(resp, sent_by_any(&all_senders));
+        assert_eq!(resp, all);
+    }
+
+    /// The transaction digest and sequence number identifying an event.
+    fn event_key(event: &serde_json::Value) -> (String, u64) {
+        (
+            event["transactionDigest"].as_str().unwrap().to_string(),
+            event["sequenceNumber"].as_u64().unwrap(),
+        )
+    }
+
+    /// Pages through the events after checkpoint 0 in the given sort order, one event at a time.
+    /// Only the first page reports no previous page.
+    async fn page_events(cluster: &ExecutorCluster, sort: &str) -> Vec<(String, u64)> {
+        let mut keys = vec![];
+        let mut after = String::new();
+        loop {
+            let query = format!(
+                "{{ eventConnection(first: 1, {after} filter: {{ afterCheckpoint: 0 }}, sort: {sort}) {{
+                    pageInfo {{ hasPreviousPage hasNextPage endCursor }}
+                    nodes {{ transactionDigest sequenceNumber }}
+                }} }}"
+            );
+            let resp = cluster.graphql_client.execute(query, vec![]).await.unwrap();
+            assert!(resp.get("errors").is_none(), "{}", resp);
+
+            let connection = &resp["data"]["eventConnection"];
+            assert_eq!(
+                connection["pageInfo"]["hasPreviousPage"].as_bool().unwrap(),
+                !keys.is_empty()
+            );
+            keys.extend(
+                connection["nodes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(event_key),
+            );
+            if !connection["pageInfo"]["hasNextPage"].as_bool().unwrap() {
+                return keys;
+            }
+            after = format!(
+                "after: \"{}\",",
+                connection["pageInfo"]["endCursor"].as_str().unwrap()
+            );
+        }
+    }
+
+    pub async fn test_event_timestamp_order_impl() {
+        let (cluster, senders) = prep_events_cluster().await;
+        let keys = |events: &[serde_json::Value]| -> Vec<(String, u64)> {
+            events.iter().map(event_key).collect()
+        };
+
+        let ascending = query_events(
+            &cluster,
+            "filter: { afterCheckpoint: 0 }, sort: TIMESTAMP_ASC",
+        )
+        .await;
+        let descending = query_events(
+            &cluster,
+            "filter: { afterCheckpoint: 0 }, sort: TIMESTAMP_DESC",
+        )
+        .await;
+        let mut reversed = keys(&descending);
+        reversed.reverse();
+        assert_eq!(keys(&ascending), reversed);
+
+        // Checkpoints are a second apart, so later checkpoints have later timestamps
+        let checkpoints: Vec<_> = ascending.iter().map(event_checkpoint).collect();
+        assert!(checkpoints.windows(2).all(|w| w[0] <= w[1]));
+
+        // The events of the last checkpoint share a timestamp, and are ordered by transaction
+        let tied: Vec<_> = ascending
+            .iter()
+            .filter(|event| event_checkpoint(event) == 4)
+            .collect();
+        assert!(tied
+            .windows(2)
+            .all(|w| w[0]["timestamp"] == w[1]["timestamp"]));
+        let mut tied_senders: Vec<_> = tied.iter().flat_map(|event| event_senders(event)).collect();
+        tied_senders.dedup();
+        assert_eq!(
+            tied_senders,
+            [senders[0].to_string(), senders[1].to_string()]
+        );
+
+        // Paging through the events one at a time visits each of them once, including across
+        // events sharing a timestamp
+        assert_eq!(
+            page_events(&cluster, "TIMESTAMP_ASC").await,
+            keys(&ascending)
+        );
+        assert_eq!(
+            page_events(&cluster, "TIMESTAMP_DESC").await,
+            keys(&descending)
+        );
+    }
 }
@@ -16,7 +16,7 @@ use super::{
     transaction_block::{TransactionBlock, TransactionBlockFilter},
 };
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy)]
 pub(crate) struct Address {
     pub address: SuiAddress,
 }
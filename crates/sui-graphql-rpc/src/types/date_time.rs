@@ -31,12 +31,20 @@ impl ScalarType for DateTime {
 }
 
 impl DateTime {
+    pub fn now() -> Self {
+        Self(ChronoUtc::now())
+    }
+
     pub fn from_ms(timestamp_ms: i64) -> Option<Self> {
         ChronoUtc
             .timestamp_millis_opt(timestamp_ms)
             .single()
             .map(Self)
     }
+
+    pub fn timestamp_ms(&self) -> i64 {
+        self.0.timestamp_millis()
+    }
 }
 
 impl FromStr for DateTime {
@@ -70,4 +78,14 @@ mod tests {
         let dt: &str = "2023-08-";
         assert!(DateTime::from_str(dt).is_err());
     }
+
+    #[test]
+    fn test_timestamp_ms() {
+        let date_time = DateTime::from_ms(1692459444700).unwrap();
+        assert_eq!(date_time.timestamp_ms(), 1692459444700);
+        assert_eq!(
+            DateTime::from_str("2023-08-19T15:37:24.700Z").unwrap(),
+            date_time
+        );
+    }
 }
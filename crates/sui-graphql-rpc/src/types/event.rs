@@ -1,35 +1,167 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use async_graphql::*;
+use async_graphql::{dataloader::DataLoader, *};
+use tracing::warn;
+
+use crate::{
+    context_data::{address_loader::AddressLoader, db_data_provider::PgManager},
+    error::Error,
+};
 
 use super::{
     address::Address, base64::Base64, date_time::DateTime, move_module::MoveModuleId,
-    move_type::MoveType, sui_address::SuiAddress,
+    move_type::MoveType, sui_address::SuiAddress, transaction_block::TransactionBlock,
 };
 
 #[derive(SimpleObject)]
+#[graphql(complex)]
 pub(crate) struct Event {
     /// Package id and module name of Move module that the event was emitted in
     pub sending_module_id: Option<MoveModuleId>,
     /// Package, module, and type of the event
     pub event_type: Option<MoveType>,
-    pub senders: Option<Vec<Address>>,
-    /// UTC timestamp in milliseconds since epoch (1/1/1970)
-    pub timestamp: Option<DateTime>,
+    #[graphql(skip)]
+    pub sender_addresses: Option<Vec<SuiAddress>>,
+    #[graphql(skip)]
+    pub timestamp_ms: Option<u64>,
     /// JSON string representation of the event
     pub json: Option<String>,
     /// Base64 encoded bcs bytes of the Move event
     pub bcs: Option<Base64>,
+    /// Digest of the transaction that emitted the event
+    pub transaction_digest: Option<String>,
+    /// Position of the event among the events emitted by its transaction
+    pub sequence_number: Option<u64>,
+}
+
+#[ComplexObject]
+impl Event {
+    /// Addresses of the senders of the transaction that emitted the event
+    async fn senders(&self, ctx: &Context<'_>) -> Result<Option<Vec<Address>>> {
+        let Some(addresses) = &self.sender_addresses else {
+            return Ok(None);
+        };
+
+        let loaded = ctx
+            .data_unchecked::<DataLoader<AddressLoader>>()
+            .load_many(addresses.iter().copied())
+            .await?;
+
+        Ok(Some(
+            addresses
+                .iter()
+                .filter_map(|address| loaded.get(address).copied())
+                .collect(),
+        ))
+    }
+
+    /// UTC timestamp in milliseconds since epoch (1/1/1970)
+    async fn timestamp(&self) -> Result<Option<DateTime>> {
+        let Some(timestamp_ms) = self.timestamp_ms else {
+            return Ok(None);
+        };
+
+        // A timestamp that can't be represented points at corrupt data in the indexer, rather than
+        // a missing timestamp.
+        let timestamp = plausible_timestamp(timestamp_ms).ok_or_else(|| {
+            Error::Internal(format!("Event has corrupt timestamp: {timestamp_ms}ms")).extend()
+        })?;
+
+        Ok(Some(timestamp))
+    }
+
+    /// The transaction block that emitted the event
+    async fn transaction(&self, ctx: &Context<'_>) -> Result<Option<TransactionBlock>> {
+        let Some(digest) = &self.transaction_digest else {
+            return Ok(None);
+        };
+
+        let transaction = ctx
+            .data_unchecked::<PgManager>()
+            .fetch_tx(digest)
+            .await
+            .extend()?;
+
+        // The indexer should have stored the transaction alongside its events
+        if transaction.is_none() {
+            warn!("Transaction {digest} that emitted an event was not found");
+        }
+
+        Ok(transaction)
+    }
+}
+
+pub(crate) struct EventCount {
+    pub filter: EventFilter,
+}
+
+#[derive(SimpleObject)]
+pub(crate) struct ModuleEventCount {
+    /// Package id and module name of the Move module that emitted the events
+    pub emitting_module: MoveModuleId,
+    /// Number of matching events emitted by the module
+    pub count: u64,
+}
+
+/// Number of events matching a filter, counted without fetching the events themselves.
+#[Object]
+impl EventCount {
+    /// Number of events matching the filter
+    async fn total(&self, ctx: &Context<'_>) -> Result<u64> {
+        ctx.data_unchecked::<PgManager>()
+            .fetch_event_count(self.filter.clone())
+            .await
+            .extend()
+    }
+
+    /// Number of events matching the filter, for each module that emitted any of them
+    async fn by_emitting_module(&self, ctx: &Context<'_>) -> Result<Vec<ModuleEventCount>> {
+        ctx.data_unchecked::<PgManager>()
+            .fetch_event_counts_by_emitting_module(self.filter.clone())
+            .await
+            .extend()
+    }
+}
+
+/// Last millisecond of the year 9999.
+const MAX_TIMESTAMP_MS: u64 = 253_402_300_799_999;
+
+fn plausible_timestamp(timestamp_ms: u64) -> Option<DateTime> {
+    if timestamp_ms > MAX_TIMESTAMP_MS {
+        return None;
+    }
+
+    DateTime::from_ms(timestamp_ms as i64)
 }
 
-#[derive(InputObject)]
+/// Order in which events are listed. Ties between events with the same timestamp are broken by the
+/// sequence number of their transaction, then their position in it, in the same direction.
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) enum EventSortOrder {
+    /// Oldest events first
+    TimestampAsc,
+    /// Newest events first
+    #[default]
+    TimestampDesc,
+}
+
+impl EventSortOrder {
+    pub(crate) fn is_descending(&self) -> bool {
+        matches!(self, EventSortOrder::TimestampDesc)
+    }
+}
+
+#[derive(InputObject, Clone)]
 pub(crate) struct EventFilter {
     pub sender: Option<SuiAddress>,
+    /// Limit to events sent by any of these addresses
+    pub senders: Option<Vec<SuiAddress>>,
     pub transaction_digest: Option<String>,
-    // Enhancement (post-MVP)
-    // after_checkpoint
-    // before_checkpoint
+    /// Limit to events emitted in checkpoints strictly after this one
+    pub after_checkpoint: Option<u64>,
+    /// Limit to events emitted in checkpoints strictly before this one
+    pub before_checkpoint: Option<u64>,
 
     // Cascading
     pub emitting_package: Option<SuiAddress>,
@@ -39,12 +171,96 @@ pub(crate) struct EventFilter {
     pub event_package: Option<SuiAddress>,
     pub event_module: Option<String>,
     pub event_type: Option<String>,
-    // Enhancement (post-MVP)
-    // pub start_time
-    // pub end_time
-
-    // Enhancement (post-MVP)
-    // pub any
-    // pub all
-    // pub not
+
+    /// Limit to events emitted at or after this time
+    pub start_time: Option<DateTime>,
+    /// Limit to events emitted strictly before this time
+    pub end_time: Option<DateTime>,
+
+    // Compound filters, combined with the criteria above.
+    /// Limit to events that match all of these filters
+    pub all: Option<Vec<EventFilter>>,
+    /// Limit to events that match at least one of these filters
+    pub any: Option<Vec<EventFilter>>,
+    /// Limit to events that do not match this filter
+    pub not: Option<Box<EventFilter>>,
+}
+
+impl EventFilter {
+    /// Whether the filter places no constraint on events. The modules of the cascading criteria
+    /// are only considered along with their package.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sender.is_none()
+            && self.senders.is_none()
+            && self.transaction_digest.is_none()
+            && self.after_checkpoint.is_none()
+            && self.before_checkpoint.is_none()
+            && self.emitting_package.is_none()
+            && self.event_package.is_none()
+            && self.event_type.is_none()
+            && self.start_time.is_none()
+            && self.end_time.is_none()
+            && self.all.is_none()
+            && self.any.is_none()
+            && self.not.is_none()
+    }
+
+    /// Whether every event matching this filter also matches `other`, judging only from the
+    /// criteria that `other` sets directly: they must all be set to the same value in this filter.
+    /// Compound filters in `other` are not looked into.
+    pub(crate) fn implies(&self, other: &EventFilter) -> bool {
+        fn covers<T: PartialEq>(this: &Option<T>, other: &Option<T>) -> bool {
+            other.is_none() || this == other
+        }
+
+        other.all.is_none()
+            && other.any.is_none()
+            && other.not.is_none()
+            && covers(&self.sender, &other.sender)
+            && covers(&self.senders, &other.senders)
+            && covers(&self.transaction_digest, &other.transaction_digest)
+            && covers(&self.after_checkpoint, &other.after_checkpoint)
+            && covers(&self.before_checkpoint, &other.before_checkpoint)
+            && covers(&self.emitting_package, &other.emitting_package)
+            && covers(&self.emitting_module, &other.emitting_module)
+            && covers(&self.event_package, &other.event_package)
+            && covers(&self.event_module, &other.event_module)
+            && covers(&self.event_type, &other.event_type)
+            && covers(&self.start_time, &other.start_time)
+            && covers(&self.end_time, &other.end_time)
+    }
+
+    /// The filters nested directly in this one's compound criteria.
+    pub(crate) fn nested(&self) -> impl Iterator<Item = &EventFilter> {
+        let all = self.all.iter().flatten();
+        let any = self.any.iter().flatten();
+        all.chain(any).chain(self.not.as_deref())
+    }
+
+    /// Number of levels of filters in this filter, counting itself.
+    pub(crate) fn depth(&self) -> u32 {
+        1 + self.nested().map(EventFilter::depth).max().unwrap_or(0)
+    }
+
+    /// Number of filters in this filter, counting itself and every nested filter.
+    pub(crate) fn node_count(&self) -> u32 {
+        1 + self.nested().map(EventFilter::node_count).sum::<u32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plausible_timestamp() {
+        assert_eq!(
+            plausible_timestamp(1_700_000_000_000).map(|t| t.timestamp_ms()),
+            Some(1_700_000_000_000)
+        );
+        assert!(plausible_timestamp(MAX_TIMESTAMP_MS).is_some());
+        assert!(plausible_timestamp(MAX_TIMESTAMP_MS + 1).is_none());
+        // Would be negative as an i64
+        assert!(plausible_timestamp(u64::MAX).is_none());
+    }
 }
@@ -0,0 +1,58 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_graphql::*;
+
+use super::date_time::DateTime;
+
+/// How up-to-date the data served by this RPC service is
+#[derive(SimpleObject)]
+pub(crate) struct Health {
+    /// Sequence number of the latest checkpoint indexed
+    pub latest_checkpoint: u64,
+    /// Timestamp of the latest checkpoint indexed
+    pub latest_checkpoint_timestamp: Option<DateTime>,
+    /// Milliseconds between the latest checkpoint indexed and the time of the request, according to
+    /// this service's clock
+    pub lag_ms: Option<u64>,
+}
+
+impl Health {
+    pub(crate) fn new(
+        latest_checkpoint: u64,
+        latest_checkpoint_timestamp: Option<DateTime>,
+        now: &DateTime,
+    ) -> Self {
+        // Clocks may disagree, but the service can't be ahead of the chain
+        let lag_ms = latest_checkpoint_timestamp
+            .as_ref()
+            .map(|timestamp| (now.timestamp_ms() - timestamp.timestamp_ms()).max(0) as u64);
+
+        Self {
+            latest_checkpoint,
+            latest_checkpoint_timestamp,
+            lag_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lag() {
+        let now = DateTime::from_ms(10_000).unwrap();
+
+        let health = Health::new(42, DateTime::from_ms(7_500), &now);
+        assert_eq!(health.latest_checkpoint, 42);
+        assert_eq!(health.lag_ms, Some(2_500));
+
+        // The checkpoint's timestamp is ahead of the service's clock
+        let health = Health::new(42, DateTime::from_ms(10_100), &now);
+        assert_eq!(health.lag_ms, Some(0));
+
+        let health = Health::new(42, None, &now);
+        assert_eq!(health.lag_ms, None);
+    }
+}
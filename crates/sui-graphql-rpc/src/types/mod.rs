@@ -17,6 +17,7 @@ pub(crate) mod end_of_epoch_data;
 pub(crate) mod epoch;
 pub(crate) mod event;
 pub(crate) mod gas;
+pub(crate) mod health;
 pub(crate) mod json;
 pub(crate) mod move_module;
 pub(crate) mod move_object;
@@ -211,4 +211,18 @@ impl MoveModuleId {
             })
             .extend()
     }
+
+    /// The Move module this ID refers to, or `null` if no such module exists.
+    async fn module(&self, ctx: &Context<'_>) -> Result<Option<MoveModule>> {
+        let Some(package) = ctx
+            .data_unchecked::<PgManager>()
+            .fetch_move_package(self.package, None)
+            .await
+            .extend()?
+        else {
+            return Ok(None);
+        };
+
+        package.module_impl(&self.name)
+    }
 }
@@ -9,8 +9,10 @@ use super::{
     checkpoint::{Checkpoint, CheckpointId},
     coin::Coin,
     coin_metadata::CoinMetadata,
+    date_time::DateTime,
     epoch::Epoch,
-    event::{Event, EventFilter},
+    event::{Event, EventCount, EventFilter, EventSortOrder},
+    health::Health,
     object::{Object, ObjectFilter},
     owner::{ObjectOwner, Owner},
     protocol_config::ProtocolConfigs,
@@ -42,6 +44,22 @@ impl Query {
             .extend()
     }
 
+    /// How far the data served by this RPC service lags behind the chain, judging from the latest
+    /// checkpoint indexed
+    async fn health(&self, ctx: &Context<'_>) -> Result<Health> {
+        let checkpoint = ctx
+            .data_unchecked::<PgManager>()
+            .fetch_latest_checkpoint()
+            .await
+            .extend()?;
+
+        Ok(Health::new(
+            checkpoint.sequence_number,
+            checkpoint.timestamp,
+            &DateTime::now(),
+        ))
+    }
+
     // availableRange - pending impl. on IndexerV2
     // dryRunTransactionBlock
     // coinMetadata
@@ -172,13 +190,19 @@ impl Query {
         last: Option<u64>,
         before: Option<String>,
         filter: EventFilter,
+        sort: Option<EventSortOrder>,
     ) -> Result<Option<Connection<String, Event>>> {
         ctx.data_unchecked::<PgManager>()
-            .fetch_events(first, after, last, before, filter)
+            .fetch_events(first, after, last, before, filter, sort.unwrap_or_default())
             .await
             .extend()
     }
 
+    /// Counts the events matching the filter, without fetching them.
+    async fn event_count(&self, filter: EventFilter) -> EventCount {
+        EventCount { filter }
+    }
+
     async fn object_connection(
         &self,
         ctx: &Context<'_>,
@@ -34,6 +34,11 @@ pub(crate) enum FromStrError {
 pub(crate) enum FromVecError {
     #[error("Expected SuiAddress with {} bytes, received {0}", SUI_ADDRESS_LENGTH)]
     WrongLength(usize),
+    #[error(
+        "Expected SuiAddress with between 1 and {} bytes, received {0}",
+        SUI_ADDRESS_LENGTH
+    )]
+    WrongShortLength(usize),
 }
 
 #[Scalar]
@@ -73,6 +78,20 @@ impl SuiAddress {
             .map_err(|_| FromVecError::WrongLength(bytes.as_ref().len()))
             .map(SuiAddress)
     }
+
+    /// Like `from_bytes`, but addresses shorter than `SUI_ADDRESS_LENGTH` bytes are left-padded
+    /// with zeroes, like their string representations, so that every representation of an address
+    /// compares equal.
+    pub fn from_short_bytes<T: AsRef<[u8]>>(bytes: T) -> Result<Self, FromVecError> {
+        let bytes = bytes.as_ref();
+        if bytes.is_empty() || bytes.len() > SUI_ADDRESS_LENGTH {
+            return Err(FromVecError::WrongShortLength(bytes.len()));
+        }
+
+        let mut arr = [0u8; SUI_ADDRESS_LENGTH];
+        arr[SUI_ADDRESS_LENGTH - bytes.len()..].copy_from_slice(bytes);
+        Ok(SuiAddress(arr))
+    }
 }
 
 impl TryFrom<Vec<u8>> for SuiAddress {
@@ -136,7 +155,9 @@ impl std::fmt::Display for SuiAddress {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::address::Address;
     use async_graphql::Value;
+    use std::{cmp::Ordering, collections::HashSet};
 
     const STR_ADDRESS: &str = "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
     const ARR_ADDRESS: [u8; SUI_ADDRESS_LENGTH] = [
@@ -174,6 +195,53 @@ mod tests {
         assert_eq!(SUI_ADDRESS.as_slice(), &ARR_ADDRESS);
     }
 
+    #[test]
+    fn test_from_short_bytes() {
+        let mut padded = [0u8; SUI_ADDRESS_LENGTH];
+        padded[SUI_ADDRESS_LENGTH - 2..].copy_from_slice(&[0x12, 0x34]);
+
+        let short = SuiAddress::from_short_bytes([0x12, 0x34]).unwrap();
+        assert_eq!(short, SuiAddress::from_bytes(padded).unwrap());
+        assert_eq!(short, SuiAddress::from_short_bytes(padded).unwrap());
+        assert_eq!(short, SuiAddress::from_str("0x1234").unwrap());
+        assert_eq!(
+            short,
+            SuiAddress::from_short_bytes([0, 0, 0x12, 0x34]).unwrap()
+        );
+
+        let short = Address { address: short };
+        let padded = Address {
+            address: SuiAddress::from_bytes(padded).unwrap(),
+        };
+        assert_eq!(short, padded);
+        assert_eq!(short.cmp(&padded), Ordering::Equal);
+        assert_eq!(HashSet::from([short, padded]).len(), 1);
+    }
+
+    #[test]
+    fn test_from_bytes_invalid_length() {
+        assert_eq!(
+            SuiAddress::from_bytes([0x12, 0x34]).unwrap_err(),
+            FromVecError::WrongLength(2)
+        );
+        assert_eq!(
+            SuiAddress::from_bytes([0u8; SUI_ADDRESS_LENGTH + 1]).unwrap_err(),
+            FromVecError::WrongLength(SUI_ADDRESS_LENGTH + 1)
+        );
+    }
+
+    #[test]
+    fn test_from_short_bytes_invalid_length() {
+        assert_eq!(
+            SuiAddress::from_short_bytes([0u8; 0]).unwrap_err(),
+            FromVecError::WrongShortLength(0)
+        );
+        assert_eq!(
+            SuiAddress::from_short_bytes([0u8; SUI_ADDRESS_LENGTH + 1]).unwrap_err(),
+            FromVecError::WrongShortLength(SUI_ADDRESS_LENGTH + 1)
+        );
+    }
+
     #[test]
     fn test_round_trip() {
         let value = ScalarType::to_value(&SUI_ADDRESS);
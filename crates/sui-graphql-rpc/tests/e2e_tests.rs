@@ -283,4 +283,28 @@ mod tests {
     async fn test_query_complexity_metrics() {
         test_query_complexity_metrics_impl().await;
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_move_module_id_module() {
+        test_move_module_id_module_impl().await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_event_checkpoint_range() {
+        test_event_checkpoint_range_impl().await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_event_senders() {
+        test_event_senders_impl().await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_event_timestamp_order() {
+        test_event_timestamp_order_impl().await;
+    }
 }
@@ -63,7 +63,7 @@ impl IndexerBuilder {
         // service
         let rest_api_url = format!("{}/rest", self.rest_url.unwrap());
         let fetcher = CheckpointFetcher::new(
-            sui_rest_api::Client::new(rest_api_url),
+            sui_rest_api::Client::new_unchecked(rest_api_url),
             self.last_downloaded_checkpoint,
             downloaded_checkpoint_data_sender,
         );
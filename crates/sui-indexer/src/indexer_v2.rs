@@ -54,7 +54,7 @@ impl IndexerV2 {
             );
 
         let rest_api_url = format!("{}/rest", config.rpc_client_url);
-        let rest_client = sui_rest_api::Client::new(&rest_api_url);
+        let rest_client = sui_rest_api::Client::new(&rest_api_url)?;
         let fetcher = CheckpointFetcher::new(
             rest_client.clone(),
             last_seq_from_db,
@@ -5,8 +5,9 @@ use std::str::FromStr;
 
 use diesel::prelude::*;
 use move_bytecode_utils::module_cache::GetModule;
-use move_core_types::annotated_value::MoveStruct;
+use move_core_types::annotated_value::{MoveStruct, MoveStructLayout};
 use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::StructTag;
 
 use sui_json_rpc_types::{SuiEvent, SuiMoveStruct};
 use sui_types::base_types::{ObjectID, SuiAddress};
@@ -78,6 +79,20 @@ impl StoredEvent {
     pub fn try_into_sui_event(
         self,
         module_cache: &impl GetModule,
+    ) -> Result<SuiEvent, IndexerError> {
+        self.try_into_sui_event_with_layout(|type_| {
+            Ok(MoveObject::get_layout_from_struct_tag(
+                type_.clone(),
+                module_cache,
+            )?)
+        })
+    }
+
+    /// Like `try_into_sui_event`, but the layout of the event's type comes from `layout`, so that
+    /// callers converting many events can share layouts between events of the same type.
+    pub fn try_into_sui_event_with_layout(
+        self,
+        layout: impl FnOnce(&StructTag) -> Result<MoveStructLayout, IndexerError>,
     ) -> Result<SuiEvent, IndexerError> {
         let package_id = ObjectID::from_bytes(self.package.clone()).map_err(|_e| {
             IndexerError::PersistentStorageDataCorruptionError(format!(
@@ -107,7 +122,7 @@ impl StoredEvent {
 
         let type_ = parse_sui_struct_tag(&self.event_type)?;
 
-        let layout = MoveObject::get_layout_from_struct_tag(type_.clone(), module_cache)?;
+        let layout = layout(&type_)?;
         let move_object = MoveStruct::simple_deserialize(&self.bcs, &layout)
             .map_err(|e| IndexerError::SerdeError(e.to_string()))?;
         let parsed_json = SuiMoveStruct::from(move_object).to_json_value();
@@ -137,7 +152,7 @@ impl StoredEvent {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use move_core_types::{account_address::AccountAddress, language_storage::StructTag};
+    use move_core_types::account_address::AccountAddress;
     use sui_types::event::Event;
 
     #[test]
@@ -1,37 +1,237 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::Result;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use futures::{Stream, StreamExt};
+use rand::Rng;
 use sui_types::base_types::{ObjectID, SequenceNumber};
 use sui_types::messages_checkpoint::{CertifiedCheckpointSummary, CheckpointSequenceNumber};
 use sui_types::object::Object;
 
 use crate::checkpoints::CheckpointData;
 
+/// Timeout applied to every request made by a `Client` created with `Client::new`.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `User-Agent` sent by a `Client` unless overridden with `Client::with_user_agent`.
+pub const DEFAULT_USER_AGENT: &str = concat!("sui-bridge-client/", env!("CARGO_PKG_VERSION"));
+
+/// Header carrying the request id set with `Client::with_request_id`.
+pub const X_REQUEST_ID: &str = "x-request-id";
+
+/// Returned (wrapped in an `anyhow::Error`) when a request does not complete within the client's
+/// configured timeout.
+#[derive(Debug, thiserror::Error)]
+#[error("request to {url} timed out after {timeout:?}")]
+pub struct TimeoutError {
+    pub url: String,
+    pub timeout: Duration,
+}
+
+/// Controls how a `Client` retries requests that failed with a server error (5xx) or could not
+/// connect. Client errors (4xx) are never retried.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts made for a request, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every subsequent retry.
+    pub base_delay: Duration,
+    /// Upper bound on a random delay added to every backoff delay.
+    pub jitter: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt per request.
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Delay to wait before making the `retry`-th retry (starting from 0).
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(1 << retry.min(16));
+        let jitter = if self.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+        };
+        backoff + jitter
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::no_retry()
+    }
+}
+
+/// Controls how `Client::get_full_checkpoints_range` fetches checkpoints.
+#[derive(Clone, Debug)]
+pub struct CheckpointStreamConfig {
+    /// Maximum number of checkpoints requested at the same time.
+    pub concurrency: usize,
+    /// End the stream after the first checkpoint that could not be fetched, instead of carrying
+    /// on with the rest of the range.
+    pub stop_on_error: bool,
+}
+
+impl Default for CheckpointStreamConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            stop_on_error: false,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     inner: reqwest::Client,
     base_url: String,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    auth_token: Option<String>,
+    user_agent: String,
+    request_id: Option<String>,
+    checkpoint_stream_config: CheckpointStreamConfig,
+    pool_max_idle_per_host: usize,
+    tcp_keepalive: Option<Duration>,
 }
 
 impl Client {
-    pub fn new<S: Into<String>>(base_url: S) -> Self {
+    /// Create a client for the service at `base_url`, which must be an http(s) url. A trailing
+    /// slash is stripped from the url.
+    pub fn new<S: Into<String>>(base_url: S) -> Result<Self> {
+        Self::with_timeout(base_url, DEFAULT_REQUEST_TIMEOUT)
+    }
+
+    /// Like `Client::new`, but without validating `base_url`, for callers that already have.
+    pub fn new_unchecked<S: Into<String>>(base_url: S) -> Self {
+        Self::build(base_url.into(), DEFAULT_REQUEST_TIMEOUT)
+    }
+
+    /// Create a client whose requests fail with a `TimeoutError` if they take longer than
+    /// `timeout` to complete (including reading the response body).
+    pub fn with_timeout<S: Into<String>>(base_url: S, timeout: Duration) -> Result<Self> {
+        let base_url = normalize_base_url(base_url.into())?;
+        Ok(Self::build(base_url, timeout))
+    }
+
+    /// Create a client that authenticates every request with `Authorization: Bearer <token>`.
+    pub fn with_auth_token<S: Into<String>, T: Into<String>>(
+        base_url: S,
+        token: T,
+    ) -> Result<Self> {
+        Ok(Self {
+            auth_token: Some(token.into()),
+            ..Self::new(base_url)?
+        })
+    }
+
+    fn build(base_url: String, timeout: Duration) -> Self {
+        let pool_max_idle_per_host = usize::MAX;
+        let tcp_keepalive = None;
+
         Self {
-            inner: reqwest::Client::new(),
-            base_url: base_url.into(),
+            inner: http_client(timeout, pool_max_idle_per_host, tcp_keepalive),
+            base_url,
+            timeout,
+            retry_policy: RetryPolicy::default(),
+            auth_token: None,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            request_id: None,
+            checkpoint_stream_config: CheckpointStreamConfig::default(),
+            pool_max_idle_per_host,
+            tcp_keepalive,
         }
     }
 
+    /// Keep at most `max` idle connections per host in the client's connection pool, for reuse by
+    /// later requests (by default, there is no limit). A high-QPS indexer talking to a single
+    /// fullnode is well served by keeping around as many connections as it makes concurrent
+    /// requests (e.g. `CheckpointStreamConfig::concurrency`), so that bursts do not keep opening
+    /// fresh connections.
+    ///
+    /// This replaces the client's connection pool, so it should be set before the client is used
+    /// or copied with `with_request_id`.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self.rebuild_http_client()
+    }
+
+    /// Send TCP keepalive probes on the client's connections every `interval`, or not at all if
+    /// `None` (the default). An interval of around a minute keeps idle pooled connections from
+    /// being silently dropped by load balancers between bursts of requests.
+    ///
+    /// Like `with_pool_max_idle_per_host`, this replaces the client's connection pool.
+    pub fn with_tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self.rebuild_http_client()
+    }
+
+    fn rebuild_http_client(mut self) -> Self {
+        self.inner = http_client(
+            self.timeout,
+            self.pool_max_idle_per_host,
+            self.tcp_keepalive,
+        );
+        self
+    }
+
+    /// Retry failed requests according to `retry_policy`. All attempts for a request share the
+    /// client's timeout, so no retry is made once the timeout would be exceeded.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Fetch checkpoint ranges according to `config`.
+    pub fn with_checkpoint_stream_config(mut self, config: CheckpointStreamConfig) -> Self {
+        self.checkpoint_stream_config = config;
+        self
+    }
+
+    /// Identify the client to the service with `user_agent`, instead of `DEFAULT_USER_AGENT`.
+    pub fn with_user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// A copy of this client that tags its requests with an `X-Request-Id: <request_id>` header,
+    /// so they can be correlated with the service's logs. The copy shares this client's
+    /// connection pool, so it is cheap to create one per call, as in
+    /// `client.with_request_id(id).get_object(object_id)`.
+    pub fn with_request_id<S: Into<String>>(&self, request_id: S) -> Self {
+        Self {
+            request_id: Some(request_id.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Probe the service's health endpoint. Returns `Ok(true)` if it responded with `200 OK`,
+    /// `Ok(false)` if it responded with any other status, and an error if it could not be reached.
+    /// The probe is never retried, so that it reports on the service as it is right now.
+    pub async fn health_check(&self) -> Result<bool> {
+        let url = format!("{}/", self.base_url);
+
+        let response = self
+            .request(&url, crate::TEXT_PLAIN_UTF_8, self.timeout)
+            .send()
+            .await
+            .map_err(|e| self.map_error(e))?;
+        Ok(response.status() == reqwest::StatusCode::OK)
+    }
+
     pub async fn get_latest_checkpoint(&self) -> Result<CertifiedCheckpointSummary> {
         let url = format!("{}/checkpoints", self.base_url);
-        let checkpoint = self
-            .inner
-            .get(url)
-            .header(reqwest::header::ACCEPT, crate::APPLICATION_JSON)
-            .send()
-            .await?
-            .json()
-            .await?;
+
+        let response = self.get(url, crate::APPLICATION_JSON).await?;
+        let checkpoint = response.json().await.map_err(|e| self.map_error(e))?;
         Ok(checkpoint)
     }
 
@@ -44,29 +244,44 @@ impl Client {
             self.base_url
         );
 
-        let bytes = self
-            .inner
-            .get(url)
-            .header(reqwest::header::ACCEPT, crate::APPLICATION_BCS)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        let response = self.get(url, crate::APPLICATION_BCS).await?;
+        let bytes = response.bytes().await.map_err(|e| self.map_error(e))?;
 
         bcs::from_bytes(&bytes).map_err(Into::into)
     }
 
+    /// Fetch the full checkpoints with sequence numbers in `[start, end)`, yielding them in order.
+    /// Up to `CheckpointStreamConfig::concurrency` checkpoints are requested at a time. A
+    /// checkpoint that could not be fetched is yielded as an error, after which the stream
+    /// carries on with the next checkpoint, unless `CheckpointStreamConfig::stop_on_error` is set.
+    pub fn get_full_checkpoints_range(
+        &self,
+        start: CheckpointSequenceNumber,
+        end: CheckpointSequenceNumber,
+    ) -> impl Stream<Item = Result<CheckpointData>> + '_ {
+        let CheckpointStreamConfig {
+            concurrency,
+            stop_on_error,
+        } = self.checkpoint_stream_config;
+
+        futures::stream::iter(start..end)
+            .map(move |sequence_number| self.get_full_checkpoint(sequence_number))
+            .buffered(concurrency.max(1))
+            .scan(false, move |failed, checkpoint| {
+                if *failed {
+                    return futures::future::ready(None);
+                }
+
+                *failed = stop_on_error && checkpoint.is_err();
+                futures::future::ready(Some(checkpoint))
+            })
+    }
+
     pub async fn get_object(&self, object_id: ObjectID) -> Result<Object> {
         let url = format!("{}/objects/{object_id}", self.base_url);
 
-        let bytes = self
-            .inner
-            .get(url)
-            .header(reqwest::header::ACCEPT, crate::APPLICATION_BCS)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        let response = self.get(url, crate::APPLICATION_BCS).await?;
+        let bytes = response.bytes().await.map_err(|e| self.map_error(e))?;
 
         bcs::from_bytes(&bytes).map_err(Into::into)
     }
@@ -78,15 +293,450 @@ impl Client {
     ) -> Result<Object> {
         let url = format!("{}/objects/{object_id}/version/{version}", self.base_url);
 
-        let bytes = self
+        let response = self.get(url, crate::APPLICATION_BCS).await?;
+        let bytes = response.bytes().await.map_err(|e| self.map_error(e))?;
+
+        bcs::from_bytes(&bytes).map_err(Into::into)
+    }
+
+    /// Issue a GET request for `url`, asking for a response of type `accept`. Responses with an
+    /// error status are returned as errors.
+    async fn get(&self, url: String, accept: &str) -> Result<reqwest::Response> {
+        self.send(url, accept)
+            .await?
+            .error_for_status()
+            .map_err(|e| self.map_error(e))
+    }
+
+    /// Send a GET request for `url`, asking for a response of type `accept`, retrying according
+    /// to the client's `RetryPolicy`. The response is returned whatever its status.
+    async fn send(&self, url: String, accept: &str) -> Result<reqwest::Response> {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut attempt = 1;
+
+        loop {
+            let timeout = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let result = self.request(&url, accept, timeout).send().await;
+
+            let retryable = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(e) => e.is_connect(),
+            };
+
+            if retryable && attempt < self.retry_policy.max_attempts {
+                let delay = self.retry_policy.delay(attempt - 1);
+                if tokio::time::Instant::now() + delay < deadline {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+            }
+
+            return result.map_err(|e| self.map_error(e));
+        }
+    }
+
+    /// A GET request to `url` carrying the client's headers, that times out after `timeout`.
+    fn request(&self, url: &str, accept: &str, timeout: Duration) -> reqwest::RequestBuilder {
+        let mut request = self
             .inner
             .get(url)
-            .header(reqwest::header::ACCEPT, crate::APPLICATION_BCS)
-            .send()
-            .await?
-            .bytes()
-            .await?;
+            .header(reqwest::header::ACCEPT, accept)
+            .header(reqwest::header::USER_AGENT, &self.user_agent)
+            .timeout(timeout);
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(request_id) = &self.request_id {
+            request = request.header(X_REQUEST_ID, request_id);
+        }
+        request
+    }
 
-        bcs::from_bytes(&bytes).map_err(Into::into)
+    /// Surface timeouts as a `TimeoutError`, and pass all other errors through as is.
+    fn map_error(&self, error: reqwest::Error) -> anyhow::Error {
+        if error.is_timeout() {
+            TimeoutError {
+                url: error.url().map(|u| u.to_string()).unwrap_or_default(),
+                timeout: self.timeout,
+            }
+            .into()
+        } else {
+            error.into()
+        }
+    }
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("user_agent", &self.user_agent)
+            .field("request_id", &self.request_id)
+            .field("checkpoint_stream_config", &self.checkpoint_stream_config)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field(
+                "auth_token",
+                &self.auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+fn http_client(
+    timeout: Duration,
+    pool_max_idle_per_host: usize,
+    tcp_keepalive: Option<Duration>,
+) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .pool_max_idle_per_host(pool_max_idle_per_host)
+        .tcp_keepalive(tcp_keepalive)
+        .build()
+        .expect("reqwest client configuration should be valid")
+}
+
+/// Check that `base_url` is a valid http(s) url, and strip any trailing slash from it, so that
+/// request paths can be appended to it.
+fn normalize_base_url(base_url: String) -> Result<String> {
+    let url =
+        reqwest::Url::parse(&base_url).with_context(|| format!("Invalid base url {base_url}"))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!(
+            "Unsupported scheme '{}' in base url {base_url}, expected http or https",
+            url.scheme(),
+        );
+    }
+
+    Ok(url.as_str().trim_end_matches('/').to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use axum::{
+        extract::ConnectInfo,
+        http::{
+            header::{AUTHORIZATION, USER_AGENT},
+            HeaderMap, StatusCode,
+        },
+        routing::get,
+        Router,
+    };
+    use simulacrum::Simulacrum;
+
+    use super::*;
+
+    /// Serve `app` on an ephemeral port, returning its base url.
+    fn serve(app: Router) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>()),
+        );
+        format!("http://{address}")
+    }
+
+    /// Serve `rest_router` backed by `sim` on an ephemeral port, returning its base url.
+    fn start_test_server(sim: Simulacrum) -> String {
+        serve(crate::rest_router(Arc::new(sim)))
+    }
+
+    #[tokio::test]
+    async fn get_latest_checkpoint() {
+        let mut sim = Simulacrum::new();
+        sim.create_checkpoint();
+        let expected = sim.create_checkpoint();
+
+        let client = Client::new(start_test_server(sim)).unwrap();
+        let checkpoint = client.get_latest_checkpoint().await.unwrap();
+
+        assert_eq!(checkpoint.sequence_number, expected.sequence_number);
+        assert_eq!(checkpoint.digest(), expected.digest());
+    }
+
+    #[tokio::test]
+    async fn get_full_checkpoints_range() {
+        let mut sim = Simulacrum::new();
+        for _ in 0..5 {
+            sim.create_checkpoint();
+        }
+
+        let client = Client::new(start_test_server(sim))
+            .unwrap()
+            .with_checkpoint_stream_config(CheckpointStreamConfig {
+                concurrency: 2,
+                stop_on_error: false,
+            });
+
+        let checkpoints: Vec<_> = client.get_full_checkpoints_range(1, 5).collect().await;
+        let sequence_numbers: Vec<_> = checkpoints
+            .into_iter()
+            .map(|c| c.unwrap().checkpoint_summary.sequence_number)
+            .collect();
+        assert_eq!(sequence_numbers, vec![1, 2, 3, 4]);
+
+        // Checkpoints 6 and 7 do not exist yet, but the stream carries on past them.
+        let checkpoints: Vec<_> = client.get_full_checkpoints_range(4, 8).collect().await;
+        let ok: Vec<_> = checkpoints.iter().map(|c| c.is_ok()).collect();
+        assert_eq!(ok, vec![true, true, false, false]);
+
+        let client = client.with_checkpoint_stream_config(CheckpointStreamConfig {
+            concurrency: 2,
+            stop_on_error: true,
+        });
+        let checkpoints: Vec<_> = client.get_full_checkpoints_range(4, 8).collect().await;
+        let ok: Vec<_> = checkpoints.iter().map(|c| c.is_ok()).collect();
+        assert_eq!(ok, vec![true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn request_timeout() {
+        let app = Router::new().route(
+            "/checkpoints",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "{}"
+            }),
+        );
+
+        let client = Client::with_timeout(serve(app), Duration::from_millis(100)).unwrap();
+        let error = client.get_latest_checkpoint().await.unwrap_err();
+
+        let timeout = error.downcast_ref::<TimeoutError>().unwrap();
+        assert_eq!(timeout.timeout, Duration::from_millis(100));
+    }
+
+    /// Serve a route that fails with `status` for the first `failures` requests, and reports the
+    /// total number of requests it received through the returned counter.
+    fn flaky_server(status: StatusCode, failures: u32) -> (String, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        let app = Router::new().route(
+            "/objects/:object_id",
+            get(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                        Err(status)
+                    } else {
+                        Ok(bcs::to_bytes(&Object::immutable_for_testing()).unwrap())
+                    }
+                }
+            }),
+        );
+        (serve(app), requests)
+    }
+
+    fn retry_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(10),
+            jitter: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_on_server_error() {
+        let (url, requests) = flaky_server(StatusCode::SERVICE_UNAVAILABLE, 2);
+        let client = Client::new(url).unwrap().with_retry_policy(retry_policy(3));
+
+        client.get_object(ObjectID::ZERO).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_gives_up_after_max_attempts() {
+        let (url, requests) = flaky_server(StatusCode::SERVICE_UNAVAILABLE, 5);
+        let client = Client::new(url).unwrap().with_retry_policy(retry_policy(3));
+
+        let error = client.get_object(ObjectID::ZERO).await.unwrap_err();
+        let error = error.downcast_ref::<reqwest::Error>().unwrap();
+        assert_eq!(
+            error.status(),
+            Some(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn no_retry_on_client_error() {
+        let (url, requests) = flaky_server(StatusCode::NOT_FOUND, 1);
+        let client = Client::new(url).unwrap().with_retry_policy(retry_policy(3));
+
+        client.get_object(ObjectID::ZERO).await.unwrap_err();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn bearer_auth_token() {
+        let app = Router::new().route(
+            "/objects/:object_id",
+            get(|headers: HeaderMap| async move {
+                if headers.get(AUTHORIZATION).map(|v| v.as_bytes()) == Some(b"Bearer s3cr3t") {
+                    Ok(bcs::to_bytes(&Object::immutable_for_testing()).unwrap())
+                } else {
+                    Err(StatusCode::UNAUTHORIZED)
+                }
+            }),
+        );
+        let url = serve(app);
+
+        let client = Client::with_auth_token(url.clone(), "s3cr3t").unwrap();
+        client.get_object(ObjectID::ZERO).await.unwrap();
+        assert!(!format!("{client:?}").contains("s3cr3t"));
+
+        let error = Client::new(url)
+            .unwrap()
+            .get_object(ObjectID::ZERO)
+            .await
+            .unwrap_err();
+        let error = error.downcast_ref::<reqwest::Error>().unwrap();
+        assert_eq!(error.status(), Some(reqwest::StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn user_agent_and_request_id() {
+        // Respond with the `User-Agent` and `X-Request-Id` headers of the request, separated by a
+        // newline (the latter is left empty if missing).
+        let app = Router::new().route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                let header = |name| {
+                    headers
+                        .get(name)
+                        .map(|v| v.to_str().unwrap().to_owned())
+                        .unwrap_or_default()
+                };
+                format!("{}\n{}", header(USER_AGENT.as_str()), header(X_REQUEST_ID))
+            }),
+        );
+        let url = serve(app);
+
+        let echo = |client: Client| async move {
+            let response = client.send(format!("{}/", client.base_url), crate::TEXT_PLAIN_UTF_8);
+            response.await.unwrap().text().await.unwrap()
+        };
+
+        let client = Client::new(url).unwrap();
+        assert_eq!(
+            echo(client.clone()).await,
+            format!("sui-bridge-client/{}\n", env!("CARGO_PKG_VERSION"))
+        );
+
+        let client = client.with_user_agent("my-indexer/1.2.3");
+        assert_eq!(
+            echo(client.with_request_id("b2c7a1")).await,
+            "my-indexer/1.2.3\nb2c7a1"
+        );
+
+        // The request id only applies to the copy of the client it was set on.
+        assert_eq!(echo(client).await, "my-indexer/1.2.3\n");
+    }
+
+    #[tokio::test]
+    async fn pooled_connections_are_reused() {
+        const REQUESTS: usize = 500;
+        const CONCURRENCY: usize = 16;
+
+        /// Number of connections `REQUESTS` requests are served over, by a client keeping at most
+        /// `max_idle` idle connections.
+        async fn connections(max_idle: usize) -> usize {
+            // Record the address of the peer behind every request, which is different for every
+            // connection.
+            let peers = Arc::new(Mutex::new(HashSet::new()));
+            let recorder = peers.clone();
+            let app = Router::new().route(
+                "/",
+                get(move |ConnectInfo(peer): ConnectInfo<SocketAddr>| {
+                    recorder.lock().unwrap().insert(peer);
+                    async { StatusCode::OK }
+                }),
+            );
+
+            let client = Client::new(serve(app))
+                .unwrap()
+                .with_pool_max_idle_per_host(max_idle)
+                .with_tcp_keepalive(Some(Duration::from_secs(60)));
+
+            let healthy: Vec<_> = futures::stream::iter(0..REQUESTS)
+                .map(|_| client.health_check())
+                .buffer_unordered(CONCURRENCY)
+                .collect()
+                .await;
+            assert!(healthy.into_iter().all(|h| h.unwrap()));
+
+            let peers = peers.lock().unwrap();
+            peers.len()
+        }
+
+        // Every connection served many requests, rather than each request opening a connection
+        // of its own.
+        let pooled = connections(CONCURRENCY).await;
+        assert!(pooled < REQUESTS / 4, "{pooled} connections");
+
+        // Without idle connections to reuse, requests open new connections.
+        let unpooled = connections(0).await;
+        assert!(unpooled > pooled, "{unpooled} <= {pooled} connections");
+    }
+
+    #[tokio::test]
+    async fn health_check() {
+        let client = Client::new(start_test_server(Simulacrum::new())).unwrap();
+        assert!(client.health_check().await.unwrap());
+
+        let app = Router::new().route("/", get(|| async { StatusCode::SERVICE_UNAVAILABLE }));
+        let client = Client::new(serve(app)).unwrap();
+        assert!(!client.health_check().await.unwrap());
+
+        // Nothing is listening on the discard port.
+        let client = Client::new("http://127.0.0.1:9").unwrap();
+        assert!(client.health_check().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn health_check_is_not_retried() {
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        let app = Router::new().route(
+            "/",
+            get(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { StatusCode::SERVICE_UNAVAILABLE }
+            }),
+        );
+
+        let client = Client::new(serve(app))
+            .unwrap()
+            .with_retry_policy(retry_policy(3));
+        assert!(!client.health_check().await.unwrap());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn base_url_validation() {
+        let client = Client::new("http://localhost:9000/rest/").unwrap();
+        assert_eq!(client.base_url, "http://localhost:9000/rest");
+
+        let client = Client::new("https://example.com").unwrap();
+        assert_eq!(client.base_url, "https://example.com");
+
+        assert!(Client::new("htpp://localhost:9000").is_err());
+        assert!(Client::new("localhost:9000").is_err());
+        assert!(Client::new("not a url").is_err());
     }
 }
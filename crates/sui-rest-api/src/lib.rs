@@ -10,7 +10,10 @@ pub mod node_state_getter;
 mod objects;
 
 pub use checkpoints::{CheckpointData, CheckpointTransaction};
-pub use client::Client;
+pub use client::{
+    CheckpointStreamConfig, Client, RetryPolicy, TimeoutError, DEFAULT_REQUEST_TIMEOUT,
+    DEFAULT_USER_AGENT, X_REQUEST_ID,
+};
 use node_state_getter::NodeStateGetter;
 
 async fn health_check() -> StatusCode {
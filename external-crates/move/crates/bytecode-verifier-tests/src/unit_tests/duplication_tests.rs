@@ -2,8 +2,9 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use move_binary_format::file_format::*;
+use move_binary_format::{file_format::*, IndexKind};
 use move_bytecode_verifier::DuplicationChecker;
+use move_core_types::vm_status::StatusCode;
 use proptest::prelude::*;
 
 #[test]
@@ -18,6 +19,31 @@ fn duplicated_friend_decls() {
     DuplicationChecker::verify_module(&m).unwrap_err();
 }
 
+#[test]
+fn duplicated_struct_defs() {
+    let mut m = basic_test_module();
+    let def = m.struct_defs[0].clone();
+    m.struct_defs.push(def);
+    let err = DuplicationChecker::verify_module(&m).unwrap_err();
+    assert_eq!(err.major_status(), StatusCode::DUPLICATE_ELEMENT);
+    assert_eq!(err.indices(), &vec![(IndexKind::StructDefinition, 1)]);
+}
+
+#[test]
+fn duplicated_field_defs() {
+    let mut m = basic_test_module();
+    match &mut m.struct_defs[0].field_information {
+        StructFieldInformation::Declared(fields) => {
+            let field = fields[0].clone();
+            fields.push(field);
+        }
+        StructFieldInformation::Native => unreachable!(),
+    }
+    let err = DuplicationChecker::verify_module(&m).unwrap_err();
+    assert_eq!(err.major_status(), StatusCode::DUPLICATE_ELEMENT);
+    assert_eq!(err.indices(), &vec![(IndexKind::FieldDefinition, 1)]);
+}
+
 proptest! {
     #[test]
     fn valid_duplication(module in CompiledModule::valid_strategy(20)) {
@@ -0,0 +1,264 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use move_binary_format::file_format::CompiledModule;
+use move_ir_to_bytecode::{
+    compiler::{compile_module, compile_module_interning},
+    parser::parse_module,
+};
+use move_ir_types::ast::{
+    FunctionDependency, ModuleDefinition, ModuleDependency, StructDependency,
+};
+
+const NUM_DEP_FUNCTIONS: usize = 100;
+const NUM_CALLS: usize = 500;
+const NUM_EXPLICIT_DEPS: usize = 50;
+const NUM_GENERATED_MODULES: usize = 20;
+const NUM_SHARED_IDENTIFIERS: usize = 500;
+
+fn dependency() -> CompiledModule {
+    let mut code = String::from(
+        "module 0x42.A {
+            struct Coin<T> has drop { value: u64 }
+
+            public mint(value: u64): Self.Coin<u64> {
+            label b0:
+                return Coin<u64> { value: move(value) };
+            }
+        ",
+    );
+    for i in 0..NUM_DEP_FUNCTIONS {
+        code.push_str(&format!(
+            "
+            public value_{i}(c: &Self.Coin<u64>): u64 {{
+            label b0:
+                return *&move(c).Coin<u64>::value;
+            }}
+            "
+        ));
+    }
+    code.push('}');
+    let no_deps: Vec<CompiledModule> = vec![];
+    compile_module(parse_module(&code).unwrap(), &no_deps)
+        .unwrap()
+        .0
+}
+
+/// A module making `NUM_CALLS` calls to dependency functions, cycling through `num_functions` of
+/// them.
+fn caller(num_functions: usize) -> String {
+    let mut code = String::from(
+        "module 0x42.B {
+            import 0x42.A;
+
+            public total(): u64 {
+                let c: A.Coin<u64>;
+                let total: u64;
+            label b0:
+                c = A.mint(1);
+                total = 0;
+        ",
+    );
+    for i in 0..NUM_CALLS {
+        code.push_str(&format!(
+            "total = move(total) + A.value_{}(&c);\n",
+            i % num_functions
+        ));
+    }
+    code.push_str("return move(total);\n}\n}");
+    code
+}
+
+fn bench_compile(c: &mut Criterion, name: &str, num_functions: usize) {
+    let deps = vec![dependency()];
+    let module = parse_module(&caller(num_functions)).unwrap();
+    c.bench_function(name, |b| {
+        b.iter_batched(
+            || module.clone(),
+            |module| compile_module(module, &deps).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn compile_with_dependency_calls(c: &mut Criterion) {
+    bench_compile(c, "compile_with_dependency_calls", NUM_DEP_FUNCTIONS);
+}
+
+fn compile_with_repeated_dependency_call(c: &mut Criterion) {
+    bench_compile(c, "compile_with_repeated_dependency_call", 1);
+}
+
+/// A module importing all of the `NUM_EXPLICIT_DEPS` modules, and calling a function of each of
+/// the `deps` ones, which it declares explicitly.
+fn explicit_dependency_caller(deps: std::ops::Range<usize>) -> ModuleDefinition {
+    let mut code = String::from("module 0x42.B {\n");
+    for i in 0..NUM_EXPLICIT_DEPS {
+        code.push_str(&format!("import 0x42.D{i};\n"));
+    }
+    code.push_str(
+        "public total(): u64 {
+            let total: u64;
+        label b0:
+            total = 0;
+        ",
+    );
+    for i in deps.clone() {
+        code.push_str(&format!(
+            "total = move(total) + D{i}.value(D{i}.mint({i}));\n"
+        ));
+    }
+    code.push_str("return move(total);\n}\n}");
+
+    let mut module = parse_module(&code).unwrap();
+    module.explicit_dependency_declarations = module.imports[deps.clone()]
+        .iter()
+        .zip(deps)
+        .map(|(import, i)| {
+            // Only the signatures are declared, with types qualified by the alias of the module
+            let dep = parse_module(&format!(
+                "module 0x42.D{i} {{
+                    struct Coin has drop {{ value: u64 }}
+
+                    public mint(value: u64): D{i}.Coin {{
+                    label b0:
+                        abort 0;
+                    }}
+
+                    public value(c: D{i}.Coin): u64 {{
+                    label b0:
+                        abort 0;
+                    }}
+                }}"
+            ))
+            .unwrap();
+            ModuleDependency {
+                name: import.alias,
+                structs: dep
+                    .structs
+                    .into_iter()
+                    .map(|s| StructDependency {
+                        abilities: s.value.abilities,
+                        name: s.value.name,
+                        type_formals: s.value.type_formals,
+                    })
+                    .collect(),
+                functions: dep
+                    .functions
+                    .into_iter()
+                    .map(|(name, f)| FunctionDependency {
+                        name,
+                        signature: f.value.signature,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    module
+}
+
+/// The explicit dependency declarations of a module are compiled one after the other, reusing a
+/// single context.
+fn compile_with_explicit_dependencies(c: &mut Criterion) {
+    let no_deps: Vec<CompiledModule> = vec![];
+    let module = explicit_dependency_caller(0..NUM_EXPLICIT_DEPS);
+    c.bench_function("compile_with_explicit_dependencies", |b| {
+        b.iter_batched(
+            || module.clone(),
+            |module| compile_module(module, &no_deps).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// The same declarations, spread over as many modules, so that each of them is compiled in a fresh
+/// context. Compared with `compile_with_explicit_dependencies`, this also compiles the importing
+/// module once per declaration.
+fn compile_explicit_dependencies_separately(c: &mut Criterion) {
+    let no_deps: Vec<CompiledModule> = vec![];
+    let modules: Vec<_> = (0..NUM_EXPLICIT_DEPS)
+        .map(|i| explicit_dependency_caller(i..i + 1))
+        .collect();
+    c.bench_function("compile_explicit_dependencies_separately", |b| {
+        b.iter_batched(
+            || modules.clone(),
+            |modules| {
+                for module in modules {
+                    compile_module(module, &no_deps).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// The `NUM_GENERATED_MODULES` modules of a generated package, each declaring a struct and a
+/// function for every one of `NUM_SHARED_IDENTIFIERS` names they all share.
+fn generated_package() -> Vec<ModuleDefinition> {
+    (0..NUM_GENERATED_MODULES)
+        .map(|m| {
+            let mut code = format!("module 0x42.M{m} {{\n");
+            for i in 0..NUM_SHARED_IDENTIFIERS {
+                code.push_str(&format!("struct S_{i} {{ value_{i}: u64 }}\n"));
+            }
+            for i in 0..NUM_SHARED_IDENTIFIERS {
+                code.push_str(&format!("f_{i}() {{\nlabel b0:\n    return;\n}}\n"));
+            }
+            code.push('}');
+            parse_module(&code).unwrap()
+        })
+        .collect()
+}
+
+fn shared_identifiers() -> Vec<String> {
+    (0..NUM_SHARED_IDENTIFIERS)
+        .flat_map(|i| [format!("S_{i}"), format!("value_{i}"), format!("f_{i}")])
+        .collect()
+}
+
+fn compile_generated_package(c: &mut Criterion) {
+    let no_deps: Vec<CompiledModule> = vec![];
+    let modules = generated_package();
+    c.bench_function("compile_generated_package", |b| {
+        b.iter_batched(
+            || modules.clone(),
+            |modules| {
+                for module in modules {
+                    compile_module(module, &no_deps).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// The same package, with the identifiers its modules share interned up front.
+fn compile_generated_package_interning(c: &mut Criterion) {
+    let no_deps: Vec<CompiledModule> = vec![];
+    let modules = generated_package();
+    let identifiers = shared_identifiers();
+    let identifiers: Vec<&str> = identifiers.iter().map(String::as_str).collect();
+    c.bench_function("compile_generated_package_interning", |b| {
+        b.iter_batched(
+            || modules.clone(),
+            |modules| {
+                for module in modules {
+                    compile_module_interning(module, &identifiers, &no_deps).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(
+    compile_benches,
+    compile_with_dependency_calls,
+    compile_with_repeated_dependency_call,
+    compile_with_explicit_dependencies,
+    compile_explicit_dependencies_separately,
+    compile_generated_package,
+    compile_generated_package_interning
+);
+criterion_main!(compile_benches);
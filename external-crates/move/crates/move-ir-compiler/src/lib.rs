@@ -12,18 +12,29 @@ mod unit_tests;
 use anyhow::Result;
 use move_binary_format::file_format::CompiledModule;
 use move_bytecode_source_map::source_map::SourceMap;
-use move_ir_to_bytecode::{compiler::compile_module, parser::parse_module};
+use move_ir_to_bytecode::{
+    compiler::compile_module_with_checks, parser::parse_module, CompileChecks, LocatedError,
+    DEFAULT_MAX_CONSTANT_SIZE,
+};
 
 /// An API for the compiler. Supports setting custom options.
 #[derive(Clone, Debug)]
 pub struct Compiler<'a> {
     /// Extra dependencies to compile with.
     pub deps: Vec<&'a CompiledModule>,
+    /// Maximum size of the serialized value of a constant, in bytes.
+    pub max_constant_size: usize,
+    /// Optional checks to do on top of the default ones.
+    pub checks: CompileChecks,
 }
 
 impl<'a> Compiler<'a> {
     pub fn new(deps: Vec<&'a CompiledModule>) -> Self {
-        Self { deps }
+        Self {
+            deps,
+            max_constant_size: DEFAULT_MAX_CONSTANT_SIZE,
+            checks: CompileChecks::default(),
+        }
     }
 
     /// Compiles the module.
@@ -42,8 +53,18 @@ impl<'a> Compiler<'a> {
 
     fn compile_mod(self, code: &str) -> Result<(CompiledModule, SourceMap)> {
         let parsed_module = parse_module(code)?;
-        let (compiled_module, source_map) =
-            compile_module(parsed_module, self.deps.iter().copied())?;
+        let (compiled_module, source_map) = compile_module_with_checks(
+            parsed_module,
+            self.max_constant_size,
+            self.checks,
+            self.deps.iter().copied(),
+        )
+        .map_err(|e| {
+            if let Some(err) = e.downcast_ref::<LocatedError>() {
+                err.emit(code);
+            }
+            e
+        })?;
         Ok((compiled_module, source_map))
     }
 }
@@ -0,0 +1,1216 @@
+// Copyright (c) The Move Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::unit_tests::testutils::{compile_module_string, compile_module_string_with_deps};
+use anyhow::Result;
+use move_binary_format::file_format::{
+    Bytecode, CompiledModule, FunctionDefinitionIndex, Signature, SignatureToken,
+};
+use move_bytecode_verifier::verify_module_unmetered;
+use move_ir_to_bytecode::{
+    compiler::{
+        compile_module, compile_module_extending, compile_module_inspecting,
+        compile_module_interning, compile_module_with_checks,
+        compile_module_with_max_constant_size, missing_dependencies, struct_definitions,
+    },
+    parser::parse_module,
+    CompileChecks, LocatedError, PoolStats, DEFAULT_MAX_CONSTANT_SIZE,
+};
+use move_ir_types::ast::{FunctionDependency, ModuleDependency, ModuleName, StructDependency};
+
+fn compile_module_string_with_checks(
+    code: &str,
+    checks: CompileChecks,
+    deps: Vec<CompiledModule>,
+) -> Result<CompiledModule> {
+    let module = parse_module(code).unwrap();
+    Ok(compile_module_with_checks(module, DEFAULT_MAX_CONSTANT_SIZE, checks, &deps)?.0)
+}
+
+#[test]
+fn signatures_are_deduplicated() {
+    let mut code = String::from("module 0x42.M {\n");
+    for i in 0..16 {
+        code.push_str(&format!(
+            "public f_{i}() {{\nlabel b0:\n    return;\n}}\n\
+             public g_{i}(x: u64): u64 {{\nlabel b0:\n    return move(x);\n}}\n"
+        ));
+    }
+    code.push('}');
+
+    let module = compile_module_string(&code).unwrap();
+    // Only `()` and `(u64)` are ever used, as parameters, returns and locals alike.
+    assert_eq!(module.signatures.len(), 2);
+    let empty = module
+        .signatures
+        .iter()
+        .filter(|sig| **sig == Signature(vec![]))
+        .count();
+    assert_eq!(empty, 1);
+}
+
+#[test]
+fn unbound_field_error_carries_location() {
+    let code = "
+        module 0x42.M {
+            struct T { x: u64 }
+
+            f(): Self.T {
+            label b0:
+                return T { y: 0 };
+            }
+        }
+        ";
+
+    let err = compile_module_string(code).unwrap_err();
+    assert_eq!(err.to_string(), "Unbound field y");
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "y");
+}
+
+#[test]
+fn unbound_module_alias_error_carries_location() {
+    let code = "
+        module 0x42.M {
+            f() {
+            label b0:
+                X.g();
+                return;
+            }
+        }
+        ";
+
+    let err = compile_module_string(code).unwrap_err();
+    assert_eq!(err.to_string(), "Unbound module alias X");
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "X.g");
+}
+
+#[test]
+fn duplicate_struct_definition() {
+    let code = "
+        module 0x42.M {
+            struct T { b: bool }
+            struct T { x: u64 }
+        }
+        ";
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), &[]).unwrap().0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let checks = CompileChecks {
+        duplicates: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![]).unwrap_err();
+    assert_eq!(err.to_string(), "Duplicate struct definition for T");
+    // The error points at the second definition.
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert!(located.loc.start() as usize > code.find("struct T").unwrap());
+}
+
+#[test]
+fn duplicate_field_declaration() {
+    let code = "
+        module 0x42.M {
+            struct T { f: u64, g: bool, f: u64 }
+        }
+        ";
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), &[]).unwrap().0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let checks = CompileChecks {
+        duplicates: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![]).unwrap_err();
+    assert_eq!(err.to_string(), "Duplicate field f in struct T");
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(located.loc.start() as usize, code.rfind("f: u64").unwrap());
+}
+
+#[test]
+fn same_field_name_in_different_structs() {
+    let code = "
+        module 0x42.M {
+            struct S { f: u64 }
+            struct T { f: bool }
+        }
+        ";
+
+    compile_module_string(code).unwrap();
+}
+
+#[test]
+fn wide_struct_field_lookups() {
+    let num_fields = 128;
+    let fields: Vec<String> = (0..num_fields).map(|i| format!("f_{i}: u64")).collect();
+    let values: Vec<String> = (0..num_fields).map(|i| format!("f_{i}: {i}")).collect();
+    let code = format!(
+        "
+        module 0x42.M {{
+            struct T {{ {} }}
+
+            new(): Self.T {{
+            label b0:
+                return T {{ {} }};
+            }}
+
+            last(t: &Self.T): u64 {{
+                let r: &u64;
+            label b0:
+                r = &move(t).T::f_{};
+                return *move(r);
+            }}
+        }}
+        ",
+        fields.join(", "),
+        values.join(", "),
+        num_fields - 1,
+    );
+
+    compile_module_string(&code).unwrap();
+}
+
+const GENERIC_STRUCT_MODULE: &str = "
+    module 0x42.A {
+        struct S<T> { b: bool }
+    }
+    ";
+
+#[test]
+fn dependency_struct_with_too_few_type_args() {
+    let dep = compile_module_string(GENERIC_STRUCT_MODULE).unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            f() {
+                let x: A.S;
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), [&dep])
+        .unwrap()
+        .0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let checks = CompileChecks {
+        struct_type_arity: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![dep]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid instantiation of struct A.S: expected 1 type argument(s), got 0"
+    );
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "x");
+}
+
+#[test]
+fn dependency_struct_with_too_many_type_args() {
+    let dep = compile_module_string(GENERIC_STRUCT_MODULE).unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            f() {
+                let x: A.S<u64, bool>;
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), [&dep])
+        .unwrap()
+        .0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let checks = CompileChecks {
+        struct_type_arity: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![dep]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid instantiation of struct A.S: expected 1 type argument(s), got 2"
+    );
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "x");
+}
+
+#[test]
+fn malformed_dependency_signature_type_args() {
+    // The dependency is malformed: its own struct is instantiated with the wrong arity, which
+    // would be rejected by the bounds checker had it been serialized.
+    let dep_code = "
+        module 0x42.A {
+            struct S<T> { b: bool }
+
+            public f(): Self.S<u64, bool> {
+            label b0:
+                abort 0;
+            }
+        }
+        ";
+    let no_deps: Vec<CompiledModule> = vec![];
+    let dep = compile_module(parse_module(dep_code).unwrap(), &no_deps)
+        .unwrap()
+        .0;
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            g() {
+            label b0:
+                _ = A.f();
+                return;
+            }
+        }
+        ";
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), [&dep])
+        .unwrap()
+        .0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let checks = CompileChecks {
+        struct_type_arity: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![dep]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid instantiation of struct A.S: expected 1 type argument(s), got 2"
+    );
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "A.f");
+}
+
+#[test]
+fn deeply_nested_dependency_signature() {
+    // Too deep to be serialized, so the dependency is used as compiled.
+    let depth = 300;
+    let ty = format!("{}u8{}", "vector<".repeat(depth), " >".repeat(depth));
+    let dep_code = format!(
+        "
+        module 0x42.A {{
+            public f(): {ty} {{
+            label b0:
+                abort 0;
+            }}
+        }}
+        "
+    );
+    let no_deps: Vec<CompiledModule> = vec![];
+    let dep = compile_module(parse_module(&dep_code).unwrap(), &no_deps)
+        .unwrap()
+        .0;
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            g() {
+            label b0:
+                _ = A.f();
+                return;
+            }
+        }
+        ";
+
+    let err = compile_module_string_with_deps(code, vec![dep]).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("exceeds the maximum nesting depth of 256"));
+}
+
+#[test]
+fn call_dependency_function_with_too_many_type_args() {
+    let dep = compile_module_string(
+        "
+        module 0x42.A {
+            public id<T>(x: T): T {
+            label b0:
+                return move(x);
+            }
+        }
+        ",
+    )
+    .unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            f(): u64 {
+            label b0:
+                return A.id<u64, bool>(0);
+            }
+        }
+        ";
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), [&dep])
+        .unwrap()
+        .0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let checks = CompileChecks {
+        call_type_arity: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![dep]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid instantiation of function A.id: expected 1 type argument(s), got 2"
+    );
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "A.id<u64, bool>");
+}
+
+#[test]
+fn dependency_struct_missing_required_ability() {
+    let dep = compile_module_string(
+        "
+        module 0x42.A {
+            struct S { b: bool }
+            struct Box<T> has copy { t: T }
+            struct Tag<phantom T> has copy { b: bool }
+
+            public make<T: copy>(): u64 {
+            label b0:
+                abort 0;
+            }
+        }
+        ",
+    )
+    .unwrap();
+    let call = |ty: &str| {
+        format!(
+            "
+            module 0x42.B {{
+                import 0x42.A;
+
+                f() {{
+                label b0:
+                    _ = A.make<{ty}>();
+                    return;
+                }}
+            }}
+            "
+        )
+    };
+    let checks = CompileChecks {
+        dependency_abilities: true,
+        ..CompileChecks::default()
+    };
+
+    // Left to the bytecode verifier by default
+    let code = call("A.S");
+    let module = compile_module(parse_module(&code).unwrap(), [&dep])
+        .unwrap()
+        .0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let err = compile_module_string_with_checks(&code, checks, vec![dep.clone()]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Struct A.S is missing required abilities: copy"
+    );
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "A.make<A.S>");
+
+    // Nested in a type argument that needs it
+    let code = call("A.Box<A.S>");
+    let err = compile_module_string_with_checks(&code, checks, vec![dep.clone()]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Struct A.S is missing required abilities: copy"
+    );
+
+    // But not as a phantom type argument
+    let code = call("A.Tag<A.S>");
+    compile_module_string_with_checks(&code, checks, vec![dep]).unwrap();
+}
+
+const PHANTOM_BOX_MODULE: &str = "
+    module 0x42.A {
+        struct Box<phantom T> { b: bool }
+        struct Cell<T> { t: T }
+    }
+    ";
+
+#[test]
+fn phantom_argument_to_dependency_struct() {
+    let dep = compile_module_string(PHANTOM_BOX_MODULE).unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            struct Wrapper<phantom T> { inner: A.Box<T>, boxes: vector<A.Box<T> > }
+        }
+        ";
+
+    let checks = CompileChecks {
+        phantom_positions: true,
+        ..CompileChecks::default()
+    };
+    let module = compile_module_string_with_checks(code, checks, vec![dep]).unwrap();
+    let phantoms: Vec<_> = module
+        .struct_handles
+        .iter()
+        .map(|handle| handle.type_parameters[0].is_phantom)
+        .collect();
+    assert_eq!(phantoms, vec![true, true]);
+}
+
+#[test]
+fn phantom_in_non_phantom_position() {
+    let dep = compile_module_string(PHANTOM_BOX_MODULE).unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            struct Wrapper<phantom T> { inner: A.Box<T>, values: vector<T> }
+        }
+        ";
+    let checks = CompileChecks {
+        phantom_positions: true,
+        ..CompileChecks::default()
+    };
+
+    // Left to the bytecode verifier by default
+    let module = compile_module(parse_module(code).unwrap(), [&dep])
+        .unwrap()
+        .0;
+    assert!(verify_module_unmetered(&module).is_err());
+
+    let err = compile_module_string_with_checks(code, checks, vec![dep.clone()]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Phantom type parameter T used in a non-phantom position in field values"
+    );
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "values");
+
+    // The phantom flags of dependency structs are resolved from the dependency
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            struct Wrapper<phantom T> { inner: A.Box<A.Cell<T> >, cell: A.Cell<A.Box<T> >, values: A.Cell<T> }
+        }
+        ";
+    let err = compile_module_string_with_checks(code, checks, vec![dep]).unwrap_err();
+    let located = err.downcast_ref::<LocatedError>().unwrap();
+    assert_eq!(&code[located.loc.usize_range()], "values");
+}
+
+#[test]
+fn call_native_dependency_function() {
+    let dep = compile_module_string(
+        "
+        module 0x42.A {
+            native public f(x: u64): u64;
+        }
+        ",
+    )
+    .unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            public g(): u64 {
+            label b0:
+                return A.f(0);
+            }
+        }
+        ";
+
+    let module = compile_module_string_with_deps(code, vec![dep]).unwrap();
+    assert_eq!(module.function_handles.len(), 2);
+    assert!(module.function_defs[0].code.is_some());
+}
+
+#[test]
+fn compilation_is_deterministic() {
+    let dep = compile_module_string(
+        "
+        module 0x42.A {
+            struct Coin<T> { value: u64 }
+
+            public mint<T>(value: u64): Self.Coin<T> {
+            label b0:
+                return Coin<T> { value: move(value) };
+            }
+
+            public value<T>(coin: &Self.Coin<T>): u64 {
+            label b0:
+                return *&move(coin).Coin<T>::value;
+            }
+        }
+        ",
+    )
+    .unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+
+            struct Pair<T1, T2> { first: T1, second: T2 }
+
+            public pair(): Self.Pair<A.Coin<bool>, A.Coin<u64> > {
+            label b0:
+                return Pair<A.Coin<bool>, A.Coin<u64> > {
+                    first: A.mint<bool>(100),
+                    second: A.mint<u64>(1)
+                };
+            }
+
+            public total(p: &Self.Pair<A.Coin<bool>, A.Coin<u64> >): u64 {
+                let first: u64;
+            label b0:
+                first = A.value<bool>(&copy(p).Pair<A.Coin<bool>, A.Coin<u64> >::first);
+                return move(first) + A.value<u64>(&move(p).Pair<A.Coin<bool>, A.Coin<u64> >::second);
+            }
+
+            public name(): vector<u8> {
+            label b0:
+                return h\"42\";
+            }
+        }
+        ";
+
+    let serialize = |module: CompiledModule| {
+        let mut bytes = vec![];
+        module.serialize(&mut bytes).unwrap();
+        bytes
+    };
+    let first = compile_module_string_with_deps(code, vec![dep.clone()]).unwrap();
+    let second = compile_module_string_with_deps(code, vec![dep]).unwrap();
+    assert_eq!(serialize(first), serialize(second));
+}
+
+const BASE_COIN_MODULE: &str = "
+    module 0x42.coin {
+        struct Coin has drop { value: u64 }
+
+        public mint(value: u64): Self.Coin {
+        label b0:
+            return Coin { value: move(value) };
+        }
+    }
+    ";
+
+#[test]
+fn same_module_name_at_different_addresses() {
+    let base = compile_module_string(BASE_COIN_MODULE).unwrap();
+    let wrapper = compile_module_string_with_deps(
+        "
+        module 0x43.coin {
+            import 0x42.coin as base;
+
+            struct Coin<T> { inner: T }
+
+            public wrap(c: base.Coin): Self.Coin<base.Coin> {
+            label b0:
+                return Coin<base.Coin> { inner: move(c) };
+            }
+        }
+        ",
+        vec![base.clone()],
+    )
+    .unwrap();
+    let code = "
+        module 0x44.M {
+            import 0x42.coin as c1;
+            import 0x43.coin as c2;
+
+            public f(): c2.Coin<c1.Coin> {
+            label b0:
+                return c2.wrap(c1.mint(1));
+            }
+        }
+        ";
+
+    let module = compile_module_string_with_deps(code, vec![base, wrapper]).unwrap();
+    let mut coins: Vec<_> = module
+        .struct_handles
+        .iter()
+        .map(|handle| {
+            let mhandle = &module.module_handles[handle.module.0 as usize];
+            (
+                module.address_identifiers[mhandle.address.0 as usize].to_hex_literal(),
+                module.identifiers[handle.name.0 as usize].as_str(),
+                handle.type_parameters.len(),
+            )
+        })
+        .collect();
+    coins.sort();
+    assert_eq!(
+        coins,
+        vec![
+            ("0x42".to_string(), "Coin", 0),
+            ("0x43".to_string(), "Coin", 1),
+        ]
+    );
+}
+
+#[test]
+fn imported_struct_is_not_resolved_in_importing_dependency() {
+    let base = compile_module_string(BASE_COIN_MODULE).unwrap();
+    let other = compile_module_string_with_deps(
+        "
+        module 0x43.coin {
+            import 0x42.coin as base;
+
+            public burn(c: base.Coin) {
+            label b0:
+                return;
+            }
+        }
+        ",
+        vec![base.clone()],
+    )
+    .unwrap();
+    let code = "
+        module 0x44.M {
+            import 0x42.coin as c1;
+            import 0x43.coin as c2;
+
+            f(c: c2.Coin) {
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    let err = compile_module_string_with_deps(code, vec![base, other]).unwrap_err();
+    assert_eq!(err.to_string(), "Unbound struct c2.Coin");
+}
+
+#[test]
+fn list_missing_dependencies() {
+    let dep = compile_module_string(GENERIC_STRUCT_MODULE).unwrap();
+    let code = "
+        module 0x42.B {
+            import 0x42.A;
+            import 0x42.C;
+            import 0x43.A as A2;
+
+            f() {
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    let module = parse_module(code).unwrap();
+    let missing: Vec<(String, String)> = missing_dependencies(&module, [&dep])
+        .unwrap()
+        .iter()
+        .map(|ident| (ident.address.to_hex_literal(), ident.name.to_string()))
+        .collect();
+    assert_eq!(
+        missing,
+        vec![
+            ("0x43".to_string(), "A".to_string()),
+            ("0x42".to_string(), "C".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn constants_are_deduplicated() {
+    let code = "
+        module 0x42.M {
+            public f(): vector<u8> {
+            label b0:
+                return h\"42\";
+            }
+
+            public g(): vector<u8> {
+            label b0:
+                return h\"42\";
+            }
+        }
+        ";
+
+    let module = compile_module_string(code).unwrap();
+    assert_eq!(module.constant_pool.len(), 1);
+    assert_eq!(
+        module.constant_pool[0].type_,
+        SignatureToken::Vector(Box::new(SignatureToken::U8))
+    );
+}
+
+#[test]
+fn extending_a_module_keeps_pool_indices() {
+    let previous_code = "
+        module 0x42.M {
+            struct T { x: u64 }
+
+            public f(x: u64): Self.T {
+            label b0:
+                return T { x: move(x) };
+            }
+        }
+        ";
+    // Adds a function ahead of `f`, which would otherwise take over the first indices in the
+    // identifier and signature pools.
+    let code = "
+        module 0x42.M {
+            struct T { x: u64 }
+
+            public g(b: bool, v: vector<u8>): bool {
+            label b0:
+                return move(b);
+            }
+
+            public f(x: u64): Self.T {
+            label b0:
+                return T { x: move(x) };
+            }
+        }
+        ";
+
+    let no_deps: Vec<CompiledModule> = vec![];
+    let previous = compile_module_string(previous_code).unwrap();
+    let (module, _) =
+        compile_module_extending(parse_module(code).unwrap(), &previous, &no_deps).unwrap();
+    verify_module_unmetered(&module).unwrap();
+
+    assert!(module.identifiers.starts_with(&previous.identifiers));
+    assert!(module
+        .address_identifiers
+        .starts_with(&previous.address_identifiers));
+    assert!(module.signatures.starts_with(&previous.signatures));
+    assert!(module.identifiers.len() > previous.identifiers.len());
+    assert!(module.signatures.len() > previous.signatures.len());
+
+    let (unseeded, _) = compile_module(parse_module(code).unwrap(), &no_deps).unwrap();
+    assert!(!unseeded.identifiers.starts_with(&previous.identifiers));
+}
+
+#[test]
+fn extending_a_module_with_different_structs() {
+    let previous_code = "
+        module 0x42.M {
+            struct T { x: u64 }
+        }
+        ";
+    let code = "
+        module 0x42.M {
+            struct S { b: bool }
+            struct T { x: u64 }
+        }
+        ";
+
+    let no_deps: Vec<CompiledModule> = vec![];
+    let previous = compile_module_string(previous_code).unwrap();
+    let err =
+        compile_module_extending(parse_module(code).unwrap(), &previous, &no_deps).unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("does not extend the struct handles of its previous version"));
+}
+
+#[test]
+fn interned_identifiers_come_first() {
+    let code = "
+        module 0x42.M {
+            struct T { x: u64 }
+
+            f() {
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    let no_deps: Vec<CompiledModule> = vec![];
+    let (module, _) =
+        compile_module_interning(parse_module(code).unwrap(), &["f", "unused", "f"], &no_deps)
+            .unwrap();
+    verify_module_unmetered(&module).unwrap();
+
+    let names: Vec<_> = module.identifiers.iter().map(|id| id.as_str()).collect();
+    assert_eq!(names, ["f", "unused", "M", "T", "x"]);
+}
+
+#[test]
+fn import_two_modules_under_the_same_alias() {
+    let code = "
+        module 0x42.M {
+            import 0x43.A;
+            import 0x44.A;
+        }
+        ";
+
+    // The latest import wins by default
+    let module = compile_module_string(code).unwrap();
+    assert_eq!(module.module_handles.len(), 3);
+
+    let checks = CompileChecks {
+        strict_imports: true,
+        ..CompileChecks::default()
+    };
+    let err = compile_module_string_with_checks(code, checks, vec![]).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Duplicate module alias A for modules"));
+}
+
+#[test]
+fn constant_above_max_size() {
+    // 100 bytes, serialized with their length as a one byte prefix
+    let code = format!(
+        "
+        module 0x42.M {{
+            public f(): vector<u8> {{
+            label b0:
+                return h\"{}\";
+            }}
+        }}
+        ",
+        "42".repeat(100)
+    );
+
+    let no_deps: Vec<CompiledModule> = vec![];
+    let (module, _) =
+        compile_module_with_max_constant_size(parse_module(&code).unwrap(), 101, &no_deps).unwrap();
+    assert_eq!(module.constant_pool[0].data.len(), 101);
+
+    let err = compile_module_with_max_constant_size(parse_module(&code).unwrap(), 100, &no_deps)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Constant of 101 bytes exceeds the maximum constant size of 100 bytes"
+    );
+}
+
+#[test]
+fn export_pool_stats() {
+    let code = "
+        module 0x42.M {
+            struct T<X> { x: X }
+
+            f(): vector<u8> {
+            label b0:
+                return h\"42\";
+            }
+
+            g(): Self.T<u64> {
+            label b0:
+                return T<u64> { x: 7 };
+            }
+        }
+        ";
+
+    let module = compile_module_string(code).unwrap();
+    let stats = PoolStats::for_module(&module);
+
+    assert_eq!(
+        stats,
+        PoolStats {
+            module_handles: 1,
+            struct_handles: 1,
+            function_handles: 2,
+            field_handles: 0,
+            signatures: 4,
+            identifiers: 5,
+            address_identifiers: 1,
+            constants: 1,
+            struct_instantiations: 1,
+            function_instantiations: 0,
+            field_instantiations: 0,
+        }
+    );
+
+    let bytes = bcs::to_bytes(&stats).unwrap();
+    assert_eq!(bcs::from_bytes::<PoolStats>(&bytes).unwrap(), stats);
+
+    let json = serde_json::to_string(&stats).unwrap();
+    assert_eq!(serde_json::from_str::<PoolStats>(&json).unwrap(), stats);
+}
+
+#[test]
+fn branch_offsets_follow_block_order() {
+    let code = "
+        module 0x42.M {
+            f(b: bool) {
+            label b0:
+                jump_if (move(b)) b2;
+            label b1:
+                jump b3;
+            label b2:
+                jump b3;
+            label b3:
+                return;
+            }
+        }
+        ";
+
+    let module = compile_module_string(code).unwrap();
+    let code = &module.function_defs[0].code.as_ref().unwrap().code;
+    assert_eq!(
+        code,
+        &vec![
+            Bytecode::MoveLoc(0),
+            Bytecode::BrTrue(3),
+            Bytecode::Branch(4),
+            Bytecode::Branch(4),
+            Bytecode::Ret,
+        ]
+    );
+}
+
+#[test]
+fn pool_usage() {
+    let code = "
+        module 0x42.M {
+            foo() {
+            label b0:
+                return;
+            }
+
+            bar() {
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    let module = compile_module_string(code).unwrap();
+    let stats = PoolStats::for_module(&module);
+    // `M`, `foo` and `bar`
+    assert_eq!(stats.identifiers, 3);
+    // `()` only
+    assert_eq!(stats.signatures, 1);
+    assert_eq!(stats.constants, 0);
+
+    let (pool, identifiers_full) = stats.percent_full()[5];
+    assert_eq!(pool, "identifier");
+    assert_eq!(identifiers_full, 300.0 / u16::MAX as f64);
+}
+
+fn explicit_dependency(name: ModuleName, code: &str) -> ModuleDependency {
+    let module = parse_module(code).unwrap();
+    ModuleDependency {
+        name,
+        structs: module
+            .structs
+            .into_iter()
+            .map(|s| StructDependency {
+                abilities: s.value.abilities,
+                name: s.value.name,
+                type_formals: s.value.type_formals,
+            })
+            .collect(),
+        functions: module
+            .functions
+            .into_iter()
+            .map(|(name, f)| FunctionDependency {
+                name,
+                signature: f.value.signature,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn explicit_dependency_declarations() {
+    let code = "
+        module 0x42.M {
+            import 0x42.D;
+            import 0x42.E;
+
+            g(s: D.S): u64 {
+            label b0:
+                return E.f();
+            }
+        }
+        ";
+    let mut module = parse_module(code).unwrap();
+    // The declarations are compiled one after the other, reusing the same context
+    module.explicit_dependency_declarations = vec![
+        explicit_dependency(
+            module.imports[0].alias,
+            "module 0x42.D { struct S has drop { x: u64 } }",
+        ),
+        explicit_dependency(
+            module.imports[1].alias,
+            "module 0x42.E { public f(): u64 { label b0: return 0; } }",
+        ),
+    ];
+    assert!(missing_dependencies(&module, []).unwrap().is_empty());
+
+    let (module, _) = compile_module(module, &[]).unwrap();
+    let names: Vec<_> = module
+        .struct_handles
+        .iter()
+        .map(|handle| module.identifiers[handle.name.0 as usize].as_str())
+        .chain(
+            module
+                .function_handles
+                .iter()
+                .map(|handle| module.identifiers[handle.name.0 as usize].as_str()),
+        )
+        .collect();
+    assert_eq!(names, vec!["S", "g", "f"]);
+}
+
+#[test]
+fn too_many_function_type_parameters() {
+    let function = |name: &str, arity: usize| {
+        let type_parameters: Vec<_> = (0..arity).map(|i| format!("T{i}")).collect();
+        format!(
+            "module 0x42.M {{ {name}<{}>() {{ label b0: return; }} }}",
+            type_parameters.join(", ")
+        )
+    };
+
+    let max = u8::MAX as usize;
+    let code = function("f", max);
+    let module = compile_module(parse_module(&code).unwrap(), &[]).unwrap().0;
+    assert_eq!(module.function_handles[0].type_parameters.len(), max);
+
+    let code = function("g", max + 1);
+    let err = compile_module(parse_module(&code).unwrap(), &[]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Function Self.g has {} type parameters, exceeding the maximum of {}",
+            max + 1,
+            max
+        )
+    );
+}
+
+#[test]
+fn too_many_struct_type_parameters() {
+    let structure = |name: &str, arity: usize| {
+        let type_parameters: Vec<_> = (0..arity).map(|i| format!("T{i}")).collect();
+        format!(
+            "module 0x42.M {{ struct {name}<{}> {{ x: u64 }} }}",
+            type_parameters.join(", ")
+        )
+    };
+
+    let max = u8::MAX as usize;
+    let code = structure("S", max);
+    let module = compile_module(parse_module(&code).unwrap(), &[]).unwrap().0;
+    assert_eq!(module.struct_handles[0].type_parameters.len(), max);
+
+    let code = structure("T", max + 1);
+    let err = compile_module(parse_module(&code).unwrap(), &[]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Struct Self.T has {} type parameters, exceeding the maximum of {}",
+            max + 1,
+            max
+        )
+    );
+}
+
+#[test]
+fn import_under_self_alias() {
+    let code = "
+        module 0x42.M {
+            import 0x42.D;
+        }
+        ";
+
+    // The parser rejects the alias, so it is set after parsing
+    let mut module = parse_module(code).unwrap();
+    module.imports[0].alias = ModuleName::module_self();
+    let err = compile_module(module, &[]).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Invalid use of reserved module alias 'Self' for module"));
+}
+
+#[test]
+fn function_source_map_during_compilation() {
+    let code = "
+        module 0x42.M {
+            f(x: u64) {
+                let y: u64;
+            label b0:
+                y = move(x);
+                return;
+            }
+
+            g() {
+            label b0:
+                return;
+            }
+        }
+        ";
+
+    let mut inspected = vec![];
+    let (_, source_map) =
+        compile_module_inspecting(parse_module(code).unwrap(), &[], |idx, source_map| {
+            // The function just compiled is mapped, the next one is not yet
+            let function = source_map.get_function_source_map(idx).unwrap();
+            let next = FunctionDefinitionIndex(idx.0 + 1);
+            assert!(source_map.get_function_source_map(next).is_err());
+            inspected.push((idx.0, function.parameters.len(), function.locals.len()));
+        })
+        .unwrap();
+    assert_eq!(inspected, vec![(0, 1, 1), (1, 0, 0)]);
+
+    let function = source_map
+        .get_function_source_map(FunctionDefinitionIndex(0))
+        .unwrap();
+    assert_eq!(function.parameters[0].0, "x");
+    assert_eq!(function.locals[0].0, "y");
+}
+
+#[test]
+fn struct_definitions_in_source_order() {
+    let code = "
+        module 0x42.M {
+            struct B { x: u64 }
+            struct C { x: u64 }
+            struct A { x: u64 }
+        }
+        ";
+
+    let defs: Vec<_> = struct_definitions(&parse_module(code).unwrap())
+        .unwrap()
+        .into_iter()
+        .map(|(name, idx)| (name.0.to_string(), idx.0))
+        .collect();
+    assert_eq!(
+        defs,
+        vec![
+            ("B".to_string(), 0),
+            ("C".to_string(), 1),
+            ("A".to_string(), 2)
+        ]
+    );
+
+    // The compiled module defines the structs at those indices
+    let module = compile_module_string(code).unwrap();
+    let names: Vec<_> = module
+        .struct_defs
+        .iter()
+        .map(|def| {
+            let handle = &module.struct_handles[def.struct_handle.0 as usize];
+            module.identifiers[handle.name.0 as usize].to_string()
+        })
+        .collect();
+    let def_names: Vec<_> = defs.into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, def_names);
+}
@@ -7,4 +7,5 @@
 pub(crate) mod testutils;
 
 mod cfg_tests;
+mod context_tests;
 mod function_tests;
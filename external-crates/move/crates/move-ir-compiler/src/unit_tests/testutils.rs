@@ -43,3 +43,10 @@ fn compile_module_string_and_assert_no_error(
 pub fn compile_module_string(code: &str) -> Result<CompiledModule> {
     compile_module_string_and_assert_no_error(code, vec![])
 }
+
+pub fn compile_module_string_with_deps(
+    code: &str,
+    deps: Vec<CompiledModule>,
+) -> Result<CompiledModule> {
+    compile_module_string_and_assert_no_error(code, deps)
+}
@@ -2,15 +2,18 @@
 // Copyright (c) The Move Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::context::{CompiledDependency, Context, MaterializedPools, TABLE_MAX_SIZE};
+use crate::context::{
+    CompileChecks, Context, LocatedError, MaterializedPools, DEFAULT_MAX_CONSTANT_SIZE,
+    TABLE_MAX_SIZE,
+};
 use anyhow::{bail, format_err, Result};
 use move_binary_format::{
     file_format::{
         Ability, AbilitySet, Bytecode, CodeOffset, CodeUnit, CompiledModule, Constant,
-        FieldDefinition, FunctionDefinition, FunctionSignature, ModuleHandle, Signature,
-        SignatureToken, StructDefinition, StructDefinitionIndex, StructFieldInformation,
-        StructHandleIndex, StructTypeParameter, TableIndex, TypeParameterIndex, TypeSignature,
-        Visibility,
+        FieldDefinition, FunctionDefinition, FunctionDefinitionIndex, FunctionSignature,
+        ModuleHandle, Signature, SignatureToken, StructDefinition, StructDefinitionIndex,
+        StructFieldInformation, StructHandleIndex, StructTypeParameter, TableIndex,
+        TypeParameterIndex, TypeSignature, Visibility,
     },
     file_format_common::VERSION_MAX,
 };
@@ -18,6 +21,7 @@ use move_bytecode_source_map::source_map::SourceMap;
 use move_core_types::runtime_value::{MoveTypeLayout, MoveValue};
 use move_ir_types::{
     ast::{self, Bytecode as IRBytecode, Bytecode_ as IRBytecode_, *},
+    location::Loc,
     sp,
 };
 use move_symbol_pool::Symbol;
@@ -30,6 +34,9 @@ use std::{
     fmt::Write,
 };
 
+/// Pool usage, as a percentage of the maximum table size, past which compiling a module warns
+const POOL_USAGE_WARNING_PERCENT: f64 = 90.0;
+
 macro_rules! record_src_loc {
     (local: $context:expr, $var:expr) => {{
         let source_name = ($var.value.0.as_str().to_owned(), $var.loc);
@@ -310,15 +317,179 @@ fn verify_module(module: &ModuleDefinition) -> Result<()> {
     Ok(())
 }
 
+/// Lists the modules imported by `module` that are neither among `dependencies` nor declared
+/// explicitly in the module, sorted by name. Compiling the module may fail on any of them.
+pub fn missing_dependencies<'a>(
+    module: &ModuleDefinition,
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+) -> Result<Vec<ModuleIdent>> {
+    let mut context = Context::new(module.loc, HashMap::new(), module.identifier)?;
+    for dep in dependencies {
+        context.add_compiled_dependency(dep)?;
+    }
+    context.declare_import(module.identifier, ModuleName::module_self())?;
+    compile_imports(&mut context, module.imports.clone())?;
+    compile_explicit_dependency_declarations(
+        &mut context,
+        module.imports.clone(),
+        module.explicit_dependency_declarations.clone(),
+    )?;
+    Ok(context.missing_dependencies())
+}
+
+/// Lists the structs defined in `module` along with the definition index each of them is compiled
+/// to, in index order. A struct defined more than once is listed once.
+pub fn struct_definitions(
+    module: &ModuleDefinition,
+) -> Result<Vec<(StructName, StructDefinitionIndex)>> {
+    let mut context = Context::new(module.loc, HashMap::new(), module.identifier)?;
+    for s in &module.structs {
+        context.declare_struct_definition_index(s.value.name.clone(), s.loc)?;
+    }
+    Ok(context
+        .struct_definitions()
+        .map(|(name, idx)| (name.clone(), idx))
+        .collect())
+}
+
 /// Compile a module.
 pub fn compile_module<'a>(
     module: ModuleDefinition,
     dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+) -> Result<(CompiledModule, SourceMap)> {
+    compile_module_impl(
+        module,
+        None,
+        &[],
+        DEFAULT_MAX_CONSTANT_SIZE,
+        CompileChecks::default(),
+        &mut |_, _| (),
+        dependencies,
+    )
+}
+
+/// Compile a module, failing if the serialized value of any of its constants is larger than
+/// `max_constant_size` bytes.
+pub fn compile_module_with_max_constant_size<'a>(
+    module: ModuleDefinition,
+    max_constant_size: usize,
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+) -> Result<(CompiledModule, SourceMap)> {
+    compile_module_impl(
+        module,
+        None,
+        &[],
+        max_constant_size,
+        CompileChecks::default(),
+        &mut |_, _| (),
+        dependencies,
+    )
+}
+
+/// Compile a module like `compile_module_with_max_constant_size`, also doing the optional
+/// `checks`.
+pub fn compile_module_with_checks<'a>(
+    module: ModuleDefinition,
+    max_constant_size: usize,
+    checks: CompileChecks,
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+) -> Result<(CompiledModule, SourceMap)> {
+    compile_module_impl(
+        module,
+        None,
+        &[],
+        max_constant_size,
+        checks,
+        &mut |_, _| (),
+        dependencies,
+    )
+}
+
+/// Compile a module with `identifiers` interned up front: they take the first indices of the
+/// identifier pool, in the order given, and stay in it even if the module does not use them.
+pub fn compile_module_interning<'a>(
+    module: ModuleDefinition,
+    identifiers: &[&str],
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+) -> Result<(CompiledModule, SourceMap)> {
+    compile_module_impl(
+        module,
+        None,
+        identifiers,
+        DEFAULT_MAX_CONSTANT_SIZE,
+        CompileChecks::default(),
+        &mut |_, _| (),
+        dependencies,
+    )
+}
+
+/// Compile a module, calling `inspect` with the source map built so far each time a function
+/// definition has been compiled, so that tools can look up its locations while compilation
+/// carries on.
+pub fn compile_module_inspecting<'a>(
+    module: ModuleDefinition,
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+    mut inspect: impl FnMut(FunctionDefinitionIndex, &SourceMap),
+) -> Result<(CompiledModule, SourceMap)> {
+    compile_module_impl(
+        module,
+        None,
+        &[],
+        DEFAULT_MAX_CONSTANT_SIZE,
+        CompileChecks::default(),
+        &mut inspect,
+        dependencies,
+    )
+}
+
+/// Compile a module that extends `previous`, an earlier compilation of it: the identifiers,
+/// addresses and signatures it shares with `previous` keep their indices. The module must declare
+/// the struct handles of `previous` first, and in the same order, as signatures refer to them.
+pub fn compile_module_extending<'a>(
+    module: ModuleDefinition,
+    previous: &CompiledModule,
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
+) -> Result<(CompiledModule, SourceMap)> {
+    let (compiled, source_map) = compile_module_impl(
+        module,
+        Some(previous),
+        &[],
+        DEFAULT_MAX_CONSTANT_SIZE,
+        CompileChecks::default(),
+        &mut |_, _| (),
+        dependencies,
+    )?;
+    if !compiled
+        .struct_handles
+        .starts_with(&previous.struct_handles)
+    {
+        bail!(
+            "Module {} does not extend the struct handles of its previous version",
+            compiled.self_id()
+        );
+    }
+    Ok((compiled, source_map))
+}
+
+fn compile_module_impl<'a>(
+    module: ModuleDefinition,
+    previous: Option<&CompiledModule>,
+    interned: &[&str],
+    max_constant_size: usize,
+    checks: CompileChecks,
+    inspect: &mut dyn FnMut(FunctionDefinitionIndex, &SourceMap),
+    dependencies: impl IntoIterator<Item = &'a CompiledModule>,
 ) -> Result<(CompiledModule, SourceMap)> {
     verify_module(&module)?;
 
     let current_module = module.identifier;
     let mut context = Context::new(module.loc, HashMap::new(), current_module)?;
+    context.set_max_constant_size(max_constant_size);
+    context.set_checks(checks);
+    if let Some(previous) = previous {
+        context.seed_from_module(previous)?;
+    }
+    context.intern_identifiers(interned.iter().copied())?;
     for dep in dependencies {
         context.add_compiled_dependency(dep)?;
     }
@@ -347,7 +518,7 @@ pub fn compile_module<'a>(
             name: s.value.name.clone(),
         };
         let type_parameters = struct_type_parameters(&s.value.type_formals);
-        context.declare_struct_handle_index(ident, abilities, type_parameters)?;
+        context.declare_struct_handle_index(ident, abilities, type_parameters, s.loc)?;
     }
 
     for ir_constant in module.constants {
@@ -358,13 +529,28 @@ pub fn compile_module<'a>(
     }
 
     for (name, function) in &module.functions {
-        let sig = function_signature(&mut context, &function.value.signature)?;
-        context.declare_function(self_name, name.clone(), sig)?;
+        let sig = function_signature(&mut context, &function.value.signature, function.loc)?;
+        if matches!(function.value.body, FunctionBody::Native) {
+            context.declare_native_function(self_name, name.clone(), sig, function.loc)?;
+        } else {
+            context.declare_function(self_name, name.clone(), sig, function.loc)?;
+        }
     }
 
     // Compile definitions
     let struct_defs = compile_structs(&mut context, &self_name, module.structs)?;
-    let function_defs = compile_functions(&mut context, &self_name, module.functions)?;
+    let function_defs = compile_functions(&mut context, &self_name, module.functions, inspect)?;
+
+    for (pool, percent_full) in context.pool_stats().percent_full() {
+        if percent_full >= POOL_USAGE_WARNING_PERCENT {
+            log::warn!(
+                "The {} pool of module {} is {:.0}% full",
+                pool,
+                current_module,
+                percent_full
+            );
+        }
+    }
 
     let (
         MaterializedPools {
@@ -380,7 +566,6 @@ pub fn compile_module<'a>(
             struct_def_instantiations,
             field_instantiations,
         },
-        _compiled_deps,
         source_map,
     ) = context.materialize_pools();
     let module = CompiledModule {
@@ -413,21 +598,25 @@ fn compile_explicit_dependency_declarations(
     imports: Vec<ImportDefinition>,
     dependencies: Vec<ModuleDependency>,
 ) -> Result<()> {
-    let mut dependencies_acc = outer_context.take_dependencies();
+    let decl_location = outer_context.decl_location();
+    let self_module = *outer_context.module_ident(&ModuleName::module_self(), decl_location)?;
+    // A single context is reused for all of the dependencies, accumulating them as they are
+    // compiled
+    let mut context = Context::new(
+        decl_location,
+        outer_context.take_dependencies(),
+        self_module,
+    )?;
     for dependency in dependencies {
         let ModuleDependency {
             name: mname,
             structs,
             functions,
         } = dependency;
-        let current_module = outer_context.module_ident(&mname)?;
-        let mut context = Context::new(
-            outer_context.decl_location(),
-            dependencies_acc,
-            *current_module,
-        )?;
+        let current_module = *outer_context.module_ident(&mname, decl_location)?;
+        context.reset_for_next_unit(decl_location, current_module)?;
         compile_imports(&mut context, imports.clone())?;
-        let self_module_handle_idx = context.module_handle_index(&mname)?;
+        let self_module_handle_idx = context.module_handle_index(&mname, decl_location)?;
         for struct_dep in structs {
             let StructDependency {
                 abilities: abs,
@@ -437,12 +626,12 @@ fn compile_explicit_dependency_declarations(
             let sname = QualifiedStructIdent::new(mname, name);
             let ability_set = abilities(&abs);
             let kinds = struct_type_parameters(&tys);
-            context.declare_struct_handle_index(sname, ability_set, kinds)?;
+            context.declare_struct_handle_index(sname, ability_set, kinds, decl_location)?;
         }
         for function_dep in functions {
             let FunctionDependency { name, signature } = function_dep;
-            let sig = function_signature(&mut context, &signature)?;
-            context.declare_function(mname, name, sig)?;
+            let sig = function_signature(&mut context, &signature, decl_location)?;
+            context.declare_function(mname, name, sig, decl_location)?;
         }
 
         let (
@@ -459,7 +648,6 @@ fn compile_explicit_dependency_declarations(
                 struct_def_instantiations,
                 field_instantiations,
             },
-            compiled_deps,
             _source_map,
         ) = context.materialize_pools();
         let compiled_module = CompiledModule {
@@ -481,13 +669,9 @@ fn compile_explicit_dependency_declarations(
             struct_defs: vec![],
             function_defs: vec![],
         };
-        dependencies_acc = compiled_deps;
-        dependencies_acc.insert(
-            *current_module,
-            CompiledDependency::stored(compiled_module)?,
-        );
+        context.add_stored_dependency(current_module, compiled_module)?;
     }
-    outer_context.restore_dependencies(dependencies_acc);
+    outer_context.restore_dependencies(context.take_dependencies());
     Ok(())
 }
 
@@ -504,7 +688,11 @@ fn compile_friends(
 
 fn compile_imports(context: &mut Context, imports: Vec<ImportDefinition>) -> Result<()> {
     for import in imports {
-        context.declare_import(import.ident, import.alias)?;
+        if context.checks().strict_imports {
+            context.declare_import_strict(import.ident, import.alias)?;
+        } else {
+            context.declare_import(import.ident, import.alias)?;
+        }
     }
     Ok(())
 }
@@ -528,10 +716,7 @@ fn type_parameter_indexes<'a>(
 fn struct_type_parameters(ast_tys: &[ast::StructTypeParameter]) -> Vec<StructTypeParameter> {
     ast_tys
         .iter()
-        .map(|(is_phantom, _, abs)| StructTypeParameter {
-            constraints: abilities(abs),
-            is_phantom: *is_phantom,
-        })
+        .map(|(is_phantom, _, abs)| Context::struct_type_parameter(abilities(abs), *is_phantom))
         .collect()
 }
 
@@ -555,9 +740,10 @@ fn compile_types(
     context: &mut Context,
     type_parameters: &HashMap<TypeVar_, TypeParameterIndex>,
     tys: &[Type],
+    loc: Loc,
 ) -> Result<Vec<SignatureToken>> {
     tys.iter()
-        .map(|ty| compile_type(context, type_parameters, ty))
+        .map(|ty| compile_type(context, type_parameters, ty, loc))
         .collect::<Result<_>>()
 }
 
@@ -565,6 +751,7 @@ fn compile_type(
     context: &mut Context,
     type_parameters: &HashMap<TypeVar_, TypeParameterIndex>,
     ty: &Type,
+    loc: Loc,
 ) -> Result<SignatureToken> {
     Ok(match ty {
         Type::Address => SignatureToken::Address,
@@ -580,9 +767,10 @@ fn compile_type(
             context,
             type_parameters,
             inner_type,
+            loc,
         )?)),
         Type::Reference(is_mutable, inner_type) => {
-            let inner_token = Box::new(compile_type(context, type_parameters, inner_type)?);
+            let inner_token = Box::new(compile_type(context, type_parameters, inner_type, loc)?);
             if *is_mutable {
                 SignatureToken::MutableReference(inner_token)
             } else {
@@ -590,12 +778,16 @@ fn compile_type(
             }
         }
         Type::Struct(ident, tys) => {
-            let sh_idx = context.struct_handle_index(ident.clone())?;
+            let sh_idx = context.struct_handle_index(ident.clone(), loc)?;
+            // Arity of structs declared in this module is left to the bytecode verifier
+            if context.checks().struct_type_arity && ident.module != ModuleName::module_self() {
+                context.check_type_arity(ident, tys.len(), loc)?;
+            }
 
             if tys.is_empty() {
                 SignatureToken::Struct(sh_idx)
             } else {
-                let tokens = compile_types(context, type_parameters, tys)?;
+                let tokens = compile_types(context, type_parameters, tys, loc)?;
                 SignatureToken::StructInstantiation(sh_idx, tokens)
             }
         }
@@ -609,16 +801,46 @@ fn compile_type(
     })
 }
 
+/// Resolves the structs in `ty`, a type argument whose instantiation must have the `required`
+/// abilities, along with the structs in their own type arguments.
+fn resolve_type_argument(
+    context: &mut Context,
+    ty: &Type,
+    required: AbilitySet,
+    loc: Loc,
+) -> Result<()> {
+    match ty {
+        Type::Vector(inner) => resolve_type_argument(context, inner, required, loc),
+        Type::Struct(ident, tys) => {
+            context.struct_handle_index_requiring(ident.clone(), required, loc)?;
+            // The instantiation has an ability only if its non-phantom type arguments have the
+            // ability it requires
+            let required = required
+                .into_iter()
+                .fold(AbilitySet::EMPTY, |set, ability| set | ability.requires());
+            let type_parameters = context.struct_type_parameters(ident)?.to_vec();
+            for (ty, type_parameter) in tys.iter().zip(type_parameters) {
+                if !type_parameter.is_phantom {
+                    resolve_type_argument(context, ty, required, loc)?;
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
 fn function_signature(
     context: &mut Context,
     f: &ast::FunctionSignature,
+    loc: Loc,
 ) -> Result<FunctionSignature> {
     let m = type_parameter_indexes(f.type_formals.iter().map(|formal| &formal.0))?;
-    let return_ = compile_types(context, &m, &f.return_type)?;
+    let return_ = compile_types(context, &m, &f.return_type, loc)?;
     let parameters = f
         .formals
         .iter()
-        .map(|(_, ty)| compile_type(context, &m, ty))
+        .map(|(_, ty)| compile_type(context, &m, ty, loc))
         .collect::<Result<_>>()?;
     let type_parameters = f
         .type_formals
@@ -643,12 +865,19 @@ fn compile_structs(
             module: *self_name,
             name: s.value.name.clone(),
         };
-        let sh_idx = context.struct_handle_index(sident.clone())?;
+        let sh_idx = context.struct_handle_index(sident.clone(), s.loc)?;
         record_src_loc!(struct_decl: context, s.loc);
         record_src_loc!(struct_type_formals: context, &s.value.type_formals);
         let m = type_parameter_indexes(s.value.type_formals.iter().map(|formal| &formal.1))?;
-        let sd_idx = context.declare_struct_definition_index(s.value.name)?;
-        let field_information = compile_fields(context, &m, sh_idx, sd_idx, s.value.fields)?;
+        let phantoms: Vec<_> = s
+            .value
+            .type_formals
+            .iter()
+            .map(|(is_phantom, _, _)| *is_phantom)
+            .collect();
+        let sd_idx = context.declare_struct_definition_index(s.value.name, s.loc)?;
+        let field_information =
+            compile_fields(context, &m, &phantoms, sh_idx, sd_idx, s.value.fields)?;
         struct_defs.push(StructDefinition {
             struct_handle: sh_idx,
             field_information,
@@ -660,6 +889,7 @@ fn compile_structs(
 fn compile_fields(
     context: &mut Context,
     type_parameters: &HashMap<TypeVar_, TypeParameterIndex>,
+    phantoms: &[bool],
     sh_idx: StructHandleIndex,
     sd_idx: StructDefinitionIndex,
     sfields: StructDefinitionFields,
@@ -668,11 +898,28 @@ fn compile_fields(
         StructDefinitionFields::Native => StructFieldInformation::Native,
         StructDefinitionFields::Move { fields } => {
             let mut decl_fields = vec![];
-            for (decl_order, (f, ty)) in fields.into_iter().enumerate() {
+            for (f, ty) in fields {
                 let name = context.identifier_index(f.value.0)?;
                 record_src_loc!(field: context, sd_idx, f);
-                let sig_token = compile_type(context, type_parameters, &ty)?;
-                context.declare_field(sh_idx, sd_idx, f.value, sig_token.clone(), decl_order);
+                let sig_token = compile_type(context, type_parameters, &ty, f.loc)?;
+                if context.checks().phantom_positions {
+                    if let Some(idx) = context.phantom_in_non_phantom_position(&sig_token, phantoms)
+                    {
+                        let ty_var = type_parameters
+                            .iter()
+                            .find_map(|(ty_var, i)| (*i == idx).then_some(ty_var))
+                            .unwrap();
+                        return Err(LocatedError::new(
+                            f.loc,
+                            format!(
+                                "Phantom type parameter {} used in a non-phantom position in field {}",
+                                ty_var, f.value
+                            ),
+                        )
+                        .into());
+                    }
+                }
+                context.declare_field(sh_idx, sd_idx, f.value, sig_token.clone(), f.loc)?;
                 decl_fields.push(FieldDefinition {
                     name,
                     signature: TypeSignature(sig_token),
@@ -687,12 +934,18 @@ fn compile_functions(
     context: &mut Context,
     self_name: &ModuleName,
     functions: Vec<(FunctionName, Function)>,
+    inspect: &mut dyn FnMut(FunctionDefinitionIndex, &SourceMap),
 ) -> Result<Vec<FunctionDefinition>> {
     functions
         .into_iter()
         .enumerate()
         .map(|(func_index, (name, ast_function))| {
-            compile_function(context, self_name, name, ast_function, func_index)
+            let def = compile_function(context, self_name, name, ast_function, func_index)?;
+            inspect(
+                FunctionDefinitionIndex(func_index as TableIndex),
+                context.source_map(),
+            );
+            Ok(def)
         })
         .collect()
 }
@@ -751,17 +1004,20 @@ fn compile_function(
     ast_function: Function,
     function_index: usize,
 ) -> Result<FunctionDefinition> {
+    let is_native = context.is_native_function(self_name, &name);
     record_src_loc!(
         function_decl: context,
         ast_function.loc,
         function_index,
-        matches!(ast_function.value.body, FunctionBody::Native)
+        is_native
     );
     record_src_loc!(
         function_type_formals: context,
         &ast_function.value.signature.type_formals
     );
-    let fh_idx = context.function_handle(*self_name, name)?.1;
+    let fh_idx = context
+        .function_handle(*self_name, name, ast_function.loc)?
+        .1;
 
     let ast_function = ast_function.value;
 
@@ -791,14 +1047,14 @@ fn compile_function_body(
 ) -> Result<CodeUnit> {
     let mut function_frame = FunctionFrame::new(type_parameters);
     for (var, t) in formals {
-        let sig = compile_type(context, function_frame.type_parameters(), &t)?;
+        let sig = compile_type(context, function_frame.type_parameters(), &t, var.loc)?;
         function_frame.define_local(&var.value, sig.clone())?;
         record_src_loc!(parameter: context, var);
     }
 
     let mut locals_signature = Signature(vec![]);
     for (var_, t) in locals {
-        let sig = compile_type(context, function_frame.type_parameters(), &t)?;
+        let sig = compile_type(context, function_frame.type_parameters(), &t, var_.loc)?;
         function_frame.define_local(&var_.value, sig.clone())?;
         locals_signature.0.push(sig);
         record_src_loc!(local: context, var_);
@@ -830,7 +1086,7 @@ fn compile_blocks(
             block.value,
         )?;
     }
-    let fake_to_actual = context.build_index_remapping(label_to_index);
+    let fake_to_actual = context.build_index_remapping(label_to_index)?;
     remap_branch_offsets(&mut code, &fake_to_actual);
     Ok(code)
 }
@@ -927,11 +1183,12 @@ fn compile_statement(
                 context,
                 function_frame.type_parameters(),
                 &tys,
+                statement.loc,
             )?);
 
             compile_expression(context, function_frame, code, *e)?;
 
-            let def_idx = context.struct_definition_index(&name)?;
+            let def_idx = context.struct_definition_index(&name, statement.loc)?;
             if tys.is_empty() {
                 push_instr!(statement.loc, Bytecode::Unpack(def_idx));
             } else {
@@ -1061,22 +1318,23 @@ fn compile_expression(
             }
         },
         Exp_::Pack(name, ast_tys, fields) => {
-            let sig_tys = compile_types(context, function_frame.type_parameters(), &ast_tys)?;
+            let sig_tys =
+                compile_types(context, function_frame.type_parameters(), &ast_tys, exp.loc)?;
             let tokens = Signature(sig_tys);
             let type_actuals_id = context.signature_index(tokens)?;
-            let def_idx = context.struct_definition_index(&name)?;
+            let def_idx = context.struct_definition_index(&name, exp.loc)?;
 
             let self_name = ModuleName::module_self();
             let ident = QualifiedStructIdent {
                 module: self_name,
                 name: name.clone(),
             };
-            let sh_idx = context.struct_handle_index(ident)?;
+            let sh_idx = context.struct_handle_index(ident, exp.loc)?;
 
             let num_fields = fields.len();
             for (field_order, (field, e)) in fields.into_iter().enumerate() {
                 // Check that the fields are specified in order matching the definition.
-                let (_, _, decl_order) = context.field(sh_idx, field.value.clone())?;
+                let (_, _, decl_order) = context.field(sh_idx, &field.value, field.loc)?;
                 if field_order != decl_order {
                     bail!("Field {} defined out of order for struct {}", field, name);
                 }
@@ -1190,8 +1448,9 @@ fn compile_expression(
                 module: ModuleName::module_self(),
                 name: field.value.struct_name,
             };
-            let sh_idx = context.struct_handle_index(struct_ident)?;
-            let (def_idx, _, field_offset) = context.field(sh_idx, field.value.field.value)?;
+            let sh_idx = context.struct_handle_index(struct_ident, exp.loc)?;
+            let (def_idx, _, field_offset) =
+                context.field(sh_idx, &field.value.field.value, field.value.field.loc)?;
 
             function_frame.pop()?;
 
@@ -1212,6 +1471,7 @@ fn compile_expression(
                     context,
                     function_frame.type_parameters(),
                     &field.value.type_actuals,
+                    exp.loc,
                 )?);
                 let type_parameters_id = context.signature_index(tokens)?;
                 let fi_idx = context.field_instantiation_index(fh_idx, type_parameters_id)?;
@@ -1247,7 +1507,8 @@ fn compile_call(
         FunctionCall_::Builtin(function) => {
             match function {
                 Builtin::VecPack(tys, num) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecPack(type_actuals_id, num));
 
@@ -1257,7 +1518,8 @@ fn compile_call(
                     function_frame.push()?; // push the return value
                 }
                 Builtin::VecLen(tys) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecLen(type_actuals_id));
 
@@ -1265,7 +1527,8 @@ fn compile_call(
                     function_frame.push()?; // push the return value
                 }
                 Builtin::VecImmBorrow(tys) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecImmBorrow(type_actuals_id));
 
@@ -1274,7 +1537,8 @@ fn compile_call(
                     function_frame.push()?; // push the return value
                 }
                 Builtin::VecMutBorrow(tys) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecMutBorrow(type_actuals_id));
 
@@ -1283,7 +1547,8 @@ fn compile_call(
                     function_frame.push()?; // push the return value
                 }
                 Builtin::VecPushBack(tys) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecPushBack(type_actuals_id));
 
@@ -1291,7 +1556,8 @@ fn compile_call(
                     function_frame.pop()?; // pop the value
                 }
                 Builtin::VecPopBack(tys) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecPopBack(type_actuals_id));
 
@@ -1299,7 +1565,8 @@ fn compile_call(
                     function_frame.push()?; // push the value
                 }
                 Builtin::VecUnpack(tys, num) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecUnpack(type_actuals_id, num));
 
@@ -1309,7 +1576,8 @@ fn compile_call(
                     }
                 }
                 Builtin::VecSwap(tys) => {
-                    let tokens = compile_types(context, function_frame.type_parameters(), &tys)?;
+                    let tokens =
+                        compile_types(context, function_frame.type_parameters(), &tys, call.loc)?;
                     let type_actuals_id = context.signature_index(Signature(tokens))?;
                     push_instr!(call.loc, Bytecode::VecSwap(type_actuals_id));
 
@@ -1359,11 +1627,38 @@ fn compile_call(
             name,
             type_actuals,
         } => {
-            let ty_arg_tokens =
-                compile_types(context, function_frame.type_parameters(), &type_actuals)?;
+            let ty_arg_tokens = compile_types(
+                context,
+                function_frame.type_parameters(),
+                &type_actuals,
+                call.loc,
+            )?;
             let tokens = Signature(ty_arg_tokens);
             let type_actuals_id = context.signature_index(tokens)?;
-            let fh_idx = context.function_handle(module, name)?.1;
+            let fh_idx = context.function_handle(module, name.clone(), call.loc)?.1;
+            let checks = context.checks();
+            if let Some(signature) = context.function_signature(&module, &name) {
+                let expected = signature.type_parameters.len();
+                if checks.call_type_arity && expected != type_actuals.len() {
+                    return Err(LocatedError::new(
+                        call.loc,
+                        format!(
+                            "Invalid instantiation of function {}.{}: expected {} type argument(s), got {}",
+                            module,
+                            name,
+                            expected,
+                            type_actuals.len()
+                        ),
+                    )
+                    .into());
+                }
+                if checks.dependency_abilities {
+                    let constraints = signature.type_parameters.clone();
+                    for (ty, required) in type_actuals.iter().zip(constraints) {
+                        resolve_type_argument(context, ty, required, call.loc)?;
+                    }
+                }
+            }
             let fcall = if type_actuals.is_empty() {
                 Bytecode::Call(fh_idx)
             } else {
@@ -1422,12 +1717,12 @@ fn compile_function_body_bytecode(
     let mut function_frame = FunctionFrame::new(type_parameters);
     let mut locals_signature = Signature(vec![]);
     for (var, t) in formals {
-        let sig = compile_type(context, function_frame.type_parameters(), &t)?;
+        let sig = compile_type(context, function_frame.type_parameters(), &t, var.loc)?;
         function_frame.define_local(&var.value, sig.clone())?;
         record_src_loc!(parameter: context, var);
     }
     for (var_, t) in locals {
-        let sig = compile_type(context, function_frame.type_parameters(), &t)?;
+        let sig = compile_type(context, function_frame.type_parameters(), &t, var_.loc)?;
         function_frame.define_local(&var_.value, sig.clone())?;
         locals_signature.0.push(sig);
         record_src_loc!(local: context, var_);
@@ -1441,7 +1736,7 @@ fn compile_function_body_bytecode(
         context.label_index(label)?;
         compile_bytecode_block(context, &mut function_frame, &mut code, block)?;
     }
-    let fake_to_actual = context.build_index_remapping(label_to_index);
+    let fake_to_actual = context.build_index_remapping(label_to_index)?;
     remap_branch_offsets(&mut code, &fake_to_actual);
     Ok(CodeUnit {
         locals: sig_idx,
@@ -1507,9 +1802,10 @@ fn compile_bytecode(
                 context,
                 function_frame.type_parameters(),
                 &tys,
+                loc,
             )?);
             let type_actuals_id = context.signature_index(tokens)?;
-            let fh_idx = context.function_handle(m, n)?.1;
+            let fh_idx = context.function_handle(m, n, loc)?.1;
             if tys.is_empty() {
                 Bytecode::Call(fh_idx)
             } else {
@@ -1522,9 +1818,10 @@ fn compile_bytecode(
                 context,
                 function_frame.type_parameters(),
                 &tys,
+                loc,
             )?);
             let type_actuals_id = context.signature_index(tokens)?;
-            let def_idx = context.struct_definition_index(&n)?;
+            let def_idx = context.struct_definition_index(&n, loc)?;
             if tys.is_empty() {
                 Bytecode::Pack(def_idx)
             } else {
@@ -1537,9 +1834,10 @@ fn compile_bytecode(
                 context,
                 function_frame.type_parameters(),
                 &tys,
+                loc,
             )?);
             let type_actuals_id = context.signature_index(tokens)?;
-            let def_idx = context.struct_definition_index(&n)?;
+            let def_idx = context.struct_definition_index(&n, loc)?;
             if tys.is_empty() {
                 Bytecode::Unpack(def_idx)
             } else {
@@ -1556,13 +1854,13 @@ fn compile_bytecode(
         IRBytecode_::ImmBorrowLoc(sp!(_, v_)) => {
             Bytecode::ImmBorrowLoc(function_frame.get_local(&v_)?)
         }
-        IRBytecode_::MutBorrowField(name, tys, sp!(_, field_)) => {
+        IRBytecode_::MutBorrowField(name, tys, sp!(field_loc, field_)) => {
             let qualified_struct_name = QualifiedStructIdent {
                 module: ModuleName::module_self(),
                 name,
             };
-            let sh_idx = context.struct_handle_index(qualified_struct_name)?;
-            let (def_idx, _, field_offset) = context.field(sh_idx, field_)?;
+            let sh_idx = context.struct_handle_index(qualified_struct_name, loc)?;
+            let (def_idx, _, field_offset) = context.field(sh_idx, &field_, field_loc)?;
 
             let fh_idx = context.field_handle_index(def_idx, field_offset as u16)?;
             if tys.is_empty() {
@@ -1572,19 +1870,20 @@ fn compile_bytecode(
                     context,
                     function_frame.type_parameters(),
                     &tys,
+                    loc,
                 )?);
                 let type_actuals_id = context.signature_index(tokens)?;
                 let fi_idx = context.field_instantiation_index(fh_idx, type_actuals_id)?;
                 Bytecode::MutBorrowFieldGeneric(fi_idx)
             }
         }
-        IRBytecode_::ImmBorrowField(name, tys, sp!(_, field_)) => {
+        IRBytecode_::ImmBorrowField(name, tys, sp!(field_loc, field_)) => {
             let qualified_struct_name = QualifiedStructIdent {
                 module: ModuleName::module_self(),
                 name,
             };
-            let sh_idx = context.struct_handle_index(qualified_struct_name)?;
-            let (def_idx, _, field_offset) = context.field(sh_idx, field_)?;
+            let sh_idx = context.struct_handle_index(qualified_struct_name, loc)?;
+            let (def_idx, _, field_offset) = context.field(sh_idx, &field_, field_loc)?;
 
             let fh_idx = context.field_handle_index(def_idx, field_offset as u16)?;
             if tys.is_empty() {
@@ -1594,6 +1893,7 @@ fn compile_bytecode(
                     context,
                     function_frame.type_parameters(),
                     &tys,
+                    loc,
                 )?);
                 let type_actuals_id = context.signature_index(tokens)?;
                 let fi_idx = context.field_instantiation_index(fh_idx, type_actuals_id)?;
@@ -1621,42 +1921,42 @@ fn compile_bytecode(
         IRBytecode_::Shl => Bytecode::Shl,
         IRBytecode_::Shr => Bytecode::Shr,
         IRBytecode_::VecPack(ty, n) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecPack(context.signature_index(sig)?, n)
         }
         IRBytecode_::VecLen(ty) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecLen(context.signature_index(sig)?)
         }
         IRBytecode_::VecImmBorrow(ty) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecImmBorrow(context.signature_index(sig)?)
         }
         IRBytecode_::VecMutBorrow(ty) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecMutBorrow(context.signature_index(sig)?)
         }
         IRBytecode_::VecPushBack(ty) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecPushBack(context.signature_index(sig)?)
         }
         IRBytecode_::VecPopBack(ty) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecPopBack(context.signature_index(sig)?)
         }
         IRBytecode_::VecUnpack(ty, n) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecUnpack(context.signature_index(sig)?, n)
         }
         IRBytecode_::VecSwap(ty) => {
-            let tokens = compile_type(context, function_frame.type_parameters(), &ty)?;
+            let tokens = compile_type(context, function_frame.type_parameters(), &ty, loc)?;
             let sig = Signature(vec![tokens]);
             Bytecode::VecSwap(context.signature_index(sig)?)
         }
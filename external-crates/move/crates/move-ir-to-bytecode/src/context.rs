@@ -3,6 +3,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{bail, format_err, Result};
+use codespan_reporting::{
+    diagnostic::{Diagnostic, Label},
+    files::SimpleFiles,
+    term::{
+        emit,
+        termcolor::{ColorChoice, StandardStream},
+        Config,
+    },
+};
 use move_binary_format::{
     access::ModuleAccess,
     file_format::{
@@ -12,7 +21,9 @@ use move_binary_format::{
         FunctionSignature, IdentifierIndex, ModuleHandle, ModuleHandleIndex, Signature,
         SignatureIndex, SignatureToken, StructDefInstantiation, StructDefInstantiationIndex,
         StructDefinitionIndex, StructHandle, StructHandleIndex, StructTypeParameter, TableIndex,
+        TypeParameterIndex,
     },
+    file_format_common::TYPE_PARAMETER_COUNT_MAX,
     CompiledModule,
 };
 use move_bytecode_source_map::source_map::SourceMap;
@@ -27,17 +38,60 @@ use move_ir_types::{
     },
     location::Loc,
 };
-use std::{clone::Clone, collections::HashMap, hash::Hash};
+use serde::{Deserialize, Serialize};
+use std::{
+    clone::Clone,
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+    sync::{Arc, OnceLock},
+};
+
+/// An error raised by the compilation context that can be traced back to a location in the
+/// source. It displays as its bare message; callers holding the source text can use `loc` to
+/// render a full diagnostic.
+#[derive(Debug)]
+pub struct LocatedError {
+    pub loc: Loc,
+    pub message: String,
+}
+
+impl LocatedError {
+    pub fn new(loc: Loc, message: String) -> Self {
+        Self { loc, message }
+    }
+
+    /// Renders the error as a diagnostic against `code_str`, the source it was raised for.
+    pub fn emit(&self, code_str: &str) {
+        let mut files = SimpleFiles::new();
+        let id = files.add(self.loc.file_hash(), code_str.to_string());
+        let error = Diagnostic::error()
+            .with_message("Compilation Error")
+            .with_labels(vec![
+                Label::primary(id, self.loc.usize_range()).with_message(self.message.clone())
+            ]);
+        let writer = &mut StandardStream::stderr(ColorChoice::Auto);
+        emit(writer, &Config::default(), &files, &error).unwrap();
+    }
+}
+
+impl fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for LocatedError {}
 
 macro_rules! get_or_add_item_macro {
-    ($m:ident, $k_get:expr, $k_insert:expr) => {{
+    ($m:ident, $k_get:expr, $k_insert:expr, $pool:expr) => {{
         let k_key = $k_get;
         Ok(if $m.contains_key(k_key) {
             *$m.get(k_key).unwrap()
         } else {
             let len = $m.len();
             if len >= TABLE_MAX_SIZE {
-                bail!("Max table size reached!")
+                bail!("{} pool exceeded maximum size ({})", $pool, TABLE_MAX_SIZE)
             }
             let index = len as TableIndex;
             $m.insert($k_insert, index);
@@ -47,25 +101,42 @@ macro_rules! get_or_add_item_macro {
 }
 
 pub const TABLE_MAX_SIZE: usize = u16::max_value() as usize;
+/// Maximum nesting of a signature token reindexed from a dependency
+pub const MAX_TYPE_DEPTH: usize = 256;
+/// Maximum number of type parameters of a function or struct, as allowed by the binary format
+pub const MAX_TYPE_PARAMETERS: usize = TYPE_PARAMETER_COUNT_MAX as usize;
+/// Default maximum size of the serialized value of a constant, in bytes
+pub const DEFAULT_MAX_CONSTANT_SIZE: usize = 64 * 1024;
+
 fn get_or_add_item_ref<K: Clone + Eq + Hash>(
     m: &mut HashMap<K, TableIndex>,
     k: &K,
+    pool: &str,
 ) -> Result<TableIndex> {
-    get_or_add_item_macro!(m, k, k.clone())
+    get_or_add_item_macro!(m, k, k.clone(), pool)
 }
 
-fn get_or_add_item<K: Eq + Hash>(m: &mut HashMap<K, TableIndex>, k: K) -> Result<TableIndex> {
-    get_or_add_item_macro!(m, &k, k)
+fn get_or_add_item<K: Eq + Hash>(
+    m: &mut HashMap<K, TableIndex>,
+    k: K,
+    pool: &str,
+) -> Result<TableIndex> {
+    get_or_add_item_macro!(m, &k, k, pool)
 }
 
 pub fn ident_str(s: &str) -> Result<&IdentStr> {
     IdentStr::new(s)
 }
 
+fn unbound_module_alias(module_name: &ModuleName, loc: Loc) -> anyhow::Error {
+    LocatedError::new(loc, format!("Unbound module alias {}", module_name)).into()
+}
+
 #[derive(Clone, Debug)]
 pub struct CompiledDependencyView<'a> {
-    structs: HashMap<(&'a IdentStr, &'a IdentStr), TableIndex>,
+    structs: HashMap<(&'a AccountAddress, &'a IdentStr, &'a IdentStr), TableIndex>,
     functions: HashMap<&'a IdentStr, TableIndex>,
+    native_functions: HashSet<&'a IdentStr>,
 
     module_pool: &'a [ModuleHandle],
     struct_pool: &'a [StructHandle],
@@ -73,6 +144,9 @@ pub struct CompiledDependencyView<'a> {
     identifiers: &'a [Identifier],
     address_identifiers: &'a [AccountAddress],
     signature_pool: &'a [Signature],
+
+    // Signatures of the functions in `function_pool`, built on first lookup
+    function_signatures: Vec<OnceLock<Arc<FunctionSignature>>>,
 }
 
 impl<'a> CompiledDependencyView<'a> {
@@ -82,13 +156,19 @@ impl<'a> CompiledDependencyView<'a> {
 
         let self_handle = dep.self_handle_idx();
 
-        for shandle in dep.struct_handles() {
+        // keep only structs defined in the current module, structs imported by the dependency
+        // may come from a module with the same name at a different address
+        let defined_struct_handles = dep
+            .struct_handles()
+            .iter()
+            .enumerate()
+            .filter(|(_idx, shandle)| shandle.module == self_handle);
+        for (idx, shandle) in defined_struct_handles {
             let mhandle = dep.module_handle_at(shandle.module);
+            let address = dep.address_identifier_at(mhandle.address);
             let mname = dep.identifier_at(mhandle.name);
             let sname = dep.identifier_at(shandle.name);
-            // get_or_add_item gets the proper struct handle index, as `dep.struct_handles()` is
-            // properly ordered
-            get_or_add_item(&mut structs, (mname, sname))?;
+            structs.insert((address, mname, sname), idx as TableIndex);
         }
 
         // keep only functions defined in the current module
@@ -103,15 +183,28 @@ impl<'a> CompiledDependencyView<'a> {
             functions.insert(fname, idx as u16);
         }
 
+        let native_functions = dep
+            .function_defs()
+            .iter()
+            .filter(|fdef| fdef.code.is_none())
+            .map(|fdef| dep.identifier_at(dep.function_handle_at(fdef.function).name))
+            .collect();
+
         Ok(Self {
             structs,
             functions,
+            native_functions,
             module_pool: dep.module_handles(),
             struct_pool: dep.struct_handles(),
             function_pool: dep.function_handles(),
             identifiers: dep.identifiers(),
             address_identifiers: dep.address_identifiers(),
             signature_pool: dep.signatures(),
+            function_signatures: dep
+                .function_handles()
+                .iter()
+                .map(|_| OnceLock::new())
+                .collect(),
         })
     }
 
@@ -141,26 +234,33 @@ impl<'a> CompiledDependencyView<'a> {
         Some((ident, name))
     }
 
-    fn struct_handle(&self, module: &ModuleName, name: &StructName) -> Option<&'a StructHandle> {
+    fn struct_handle(&self, module: &ModuleIdent, name: &StructName) -> Option<&'a StructHandle> {
         self.structs
             .get(&(
-                ident_str(module.0.as_str()).ok()?,
+                &module.address,
+                ident_str(module.name.0.as_str()).ok()?,
                 ident_str(name.0.as_str()).ok()?,
             ))
             .and_then(|idx| self.struct_pool.get(*idx as usize))
     }
 
-    fn function_signature(&self, name: &FunctionName) -> Option<FunctionSignature> {
-        self.functions
-            .get(ident_str(name.0.as_str()).ok()?)
-            .and_then(|idx| {
-                let fh = self.function_pool.get(*idx as usize)?;
-                Some(FunctionSignature {
-                    parameters: self.signature_pool[fh.parameters.0 as usize].0.clone(),
-                    return_: self.signature_pool[fh.return_.0 as usize].0.clone(),
-                    type_parameters: fh.type_parameters.clone(),
-                })
+    fn is_native_function(&self, name: &FunctionName) -> bool {
+        ident_str(name.0.as_str())
+            .map(|name| self.native_functions.contains(name))
+            .unwrap_or(false)
+    }
+
+    fn function_signature(&self, name: &FunctionName) -> Option<Arc<FunctionSignature>> {
+        let idx = *self.functions.get(ident_str(name.0.as_str()).ok()?)? as usize;
+        let fh = self.function_pool.get(idx)?;
+        let signature = self.function_signatures[idx].get_or_init(|| {
+            Arc::new(FunctionSignature {
+                parameters: self.signature_pool[fh.parameters.0 as usize].0.clone(),
+                return_: self.signature_pool[fh.return_.0 as usize].0.clone(),
+                type_parameters: fh.type_parameters.clone(),
             })
+        });
+        Some(signature.clone())
     }
 }
 
@@ -205,6 +305,91 @@ impl<'a> CompiledDependency<'a> {
 
 pub(crate) type CompiledDependencies<'a> = HashMap<ModuleIdent, CompiledDependency<'a>>;
 
+/// Checks the compiler can do on top of the ones it always does. They are all off by default:
+/// the bytecode verifier does them too, and its tests compile modules that must reach it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompileChecks {
+    /// Fail on a struct defined twice, or on a field declared twice in a struct, instead of
+    /// keeping the first declaration.
+    pub duplicates: bool,
+    /// Fail on an alias imported for two different modules, instead of letting the latest import
+    /// win.
+    pub strict_imports: bool,
+    /// Fail on a call with a different number of type arguments than the function declares type
+    /// parameters.
+    pub call_type_arity: bool,
+    /// Fail on a dependency struct instantiated with a different number of type arguments than it
+    /// declares type parameters.
+    pub struct_type_arity: bool,
+    /// Fail on a dependency struct used as a type argument, directly or nested in another type
+    /// argument, without the abilities the type parameter requires.
+    pub dependency_abilities: bool,
+    /// Fail on a phantom type parameter used in a field type other than as the argument of a
+    /// phantom type parameter.
+    pub phantom_positions: bool,
+}
+
+/// Number of entries in each of the pools of a compilation unit.
+///
+/// Serializable so that build tooling can record pool sizes (e.g. as BCS or JSON) and track
+/// them against a budget over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub module_handles: usize,
+    pub struct_handles: usize,
+    pub function_handles: usize,
+    pub field_handles: usize,
+    pub signatures: usize,
+    pub identifiers: usize,
+    pub address_identifiers: usize,
+    pub constants: usize,
+    pub struct_instantiations: usize,
+    pub function_instantiations: usize,
+    pub field_instantiations: usize,
+}
+
+impl PoolStats {
+    /// The size of each pool in an already compiled `module`.
+    pub fn for_module(module: &CompiledModule) -> Self {
+        PoolStats {
+            module_handles: module.module_handles.len(),
+            struct_handles: module.struct_handles.len(),
+            function_handles: module.function_handles.len(),
+            field_handles: module.field_handles.len(),
+            signatures: module.signatures.len(),
+            identifiers: module.identifiers.len(),
+            address_identifiers: module.address_identifiers.len(),
+            constants: module.constant_pool.len(),
+            struct_instantiations: module.struct_def_instantiations.len(),
+            function_instantiations: module.function_instantiations.len(),
+            field_instantiations: module.field_instantiations.len(),
+        }
+    }
+
+    /// The size of each pool, along with its name.
+    pub fn pools(&self) -> [(&'static str, usize); 11] {
+        [
+            ("module handle", self.module_handles),
+            ("struct handle", self.struct_handles),
+            ("function handle", self.function_handles),
+            ("field handle", self.field_handles),
+            ("signature", self.signatures),
+            ("identifier", self.identifiers),
+            ("address identifier", self.address_identifiers),
+            ("constant", self.constants),
+            ("struct instantiation", self.struct_instantiations),
+            ("function instantiation", self.function_instantiations),
+            ("field instantiation", self.field_instantiations),
+        ]
+    }
+
+    /// How full each pool is, as a percentage of `TABLE_MAX_SIZE`.
+    pub fn percent_full(&self) -> [(&'static str, f64); 11] {
+        self.pools()
+            .map(|(pool, len)| (pool, len as f64 * 100.0 / TABLE_MAX_SIZE as f64))
+    }
+}
+
 /// Represents all of the pools to be used in the file format, both by CompiledModule
 /// and CompiledScript.
 pub struct MaterializedPools {
@@ -232,6 +417,15 @@ pub struct MaterializedPools {
     pub constant_pool: Vec<Constant>,
 }
 
+/// The fields declared by a struct definition.
+struct StructFields {
+    def_idx: StructDefinitionIndex,
+    /// Field types, in declaration order
+    tokens: Vec<SignatureToken>,
+    /// Declaration order of each field, by name
+    order: HashMap<Field_, usize>,
+}
+
 /// Compilation context for a single compilation unit (module or script).
 /// Contains all of the pools as they are built up.
 /// Specific definitions to CompiledModule or CompiledScript are not stored.
@@ -248,11 +442,12 @@ pub(crate) struct Context<'a> {
     labels: HashMap<BlockLabel_, u16>,
 
     // queryable pools
-    // TODO: lookup for Fields is not that seemless after binary format changes
-    // We need multiple lookups or a better representation for fields
-    fields: HashMap<(StructHandleIndex, Field_), (StructDefinitionIndex, SignatureToken, usize)>,
+    fields: HashMap<StructHandleIndex, StructFields>,
     function_handles: HashMap<(ModuleName, FunctionName), (FunctionHandle, FunctionHandleIndex)>,
     function_signatures: HashMap<(ModuleName, FunctionName), FunctionSignature>,
+    native_functions: HashSet<(ModuleName, FunctionName)>,
+    // Dependency signature tokens, as reindexed into this unit's pools
+    reindexed_tokens: HashMap<(ModuleIdent, SignatureToken), SignatureToken>,
 
     // Simple pools
     module_handles: HashMap<ModuleHandle, TableIndex>,
@@ -269,6 +464,15 @@ pub(crate) struct Context<'a> {
     // The current function index that we are on
     current_function_index: FunctionDefinitionIndex,
 
+    // The module being compiled
+    current_module: ModuleIdent,
+
+    // Maximum size of the serialized value of a constant, in bytes
+    max_constant_size: usize,
+
+    // Optional checks done while compiling
+    checks: CompileChecks,
+
     // Source location mapping for this module
     pub source_map: SourceMap,
 }
@@ -293,6 +497,8 @@ impl<'a> Context<'a> {
             fields: HashMap::new(),
             function_handles: HashMap::new(),
             function_signatures: HashMap::new(),
+            native_functions: HashSet::new(),
+            reindexed_tokens: HashMap::new(),
             module_handles: HashMap::new(),
             struct_handles: HashMap::new(),
             field_handles: HashMap::new(),
@@ -304,12 +510,79 @@ impl<'a> Context<'a> {
             address_identifiers: HashMap::new(),
             constant_pool: HashMap::new(),
             current_function_index: FunctionDefinitionIndex::new(0),
+            current_module,
+            max_constant_size: DEFAULT_MAX_CONSTANT_SIZE,
+            checks: CompileChecks::default(),
             source_map: SourceMap::new(decl_location, current_module),
         };
 
         Ok(context)
     }
 
+    /// Prepares the context to compile `current_module`, declared at `decl_location`.
+    ///
+    /// Retained: `dependencies` (along with the views over them), `max_constant_size` and
+    /// `checks`.
+    ///
+    /// Cleared, as they belong to the previous unit: the helpers (`aliases`, `modules`,
+    /// `structs`, `struct_defs`, `named_constants`, `labels`), the queryable pools (`fields`,
+    /// `function_handles`, `function_signatures`, `native_functions`), `reindexed_tokens`, which
+    /// index into the previous pools, all of the simple pools, `current_function_index` and the
+    /// `source_map`. `current_module` is replaced.
+    pub fn reset_for_next_unit(
+        &mut self,
+        decl_location: Loc,
+        current_module: ModuleIdent,
+    ) -> Result<()> {
+        let dependencies = self.take_dependencies();
+        let max_constant_size = self.max_constant_size;
+        let checks = self.checks;
+        *self = Self::new(decl_location, dependencies, current_module)?;
+        self.max_constant_size = max_constant_size;
+        self.checks = checks;
+        Ok(())
+    }
+
+    /// Bounds the size of the serialized value of the constants declared from now on.
+    pub fn set_max_constant_size(&mut self, max_constant_size: usize) {
+        self.max_constant_size = max_constant_size;
+    }
+
+    /// Enables the optional `checks` for the declarations from now on.
+    pub fn set_checks(&mut self, checks: CompileChecks) {
+        self.checks = checks;
+    }
+
+    /// The optional checks enabled.
+    pub fn checks(&self) -> CompileChecks {
+        self.checks
+    }
+
+    /// Adds a dependency owned by the context, replacing any previous one for `ident`.
+    pub fn add_stored_dependency(
+        &mut self,
+        ident: ModuleIdent,
+        compiled_dep: CompiledModule,
+    ) -> Result<()> {
+        self.dependencies
+            .insert(ident, CompiledDependency::stored(compiled_dep)?);
+        self.reindexed_tokens.retain(|(dep, _), _| dep != &ident);
+        Ok(())
+    }
+
+    /// The imported modules, other than the current one, for which no dependency was provided.
+    pub fn missing_dependencies(&self) -> Vec<ModuleIdent> {
+        let mut missing: Vec<ModuleIdent> = self
+            .modules
+            .values()
+            .map(|(ident, _)| *ident)
+            .filter(|ident| ident != &self.current_module && !self.dependencies.contains_key(ident))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
     pub fn take_dependencies(&mut self) -> CompiledDependencies<'a> {
         std::mem::take(&mut self.dependencies)
     }
@@ -333,6 +606,42 @@ impl<'a> Context<'a> {
         Ok(())
     }
 
+    /// Seeds the identifier, address identifier and signature pools with those of `module`, at the
+    /// same indices, so that the entries this unit shares with `module` keep their indices in it.
+    /// Must be called before anything is added to these pools.
+    pub fn seed_from_module(&mut self, module: &CompiledModule) -> Result<()> {
+        if !self.identifiers.is_empty()
+            || !self.address_identifiers.is_empty()
+            || !self.signatures.is_empty()
+        {
+            bail!("Cannot seed pools that are already populated");
+        }
+
+        self.intern_identifiers(module.identifiers().iter().map(|ident| ident.as_str()))?;
+        for addr in module.address_identifiers() {
+            self.address_index(*addr)?;
+        }
+        for sig in module.signatures() {
+            self.signature_index(sig.clone())?;
+        }
+
+        // Duplicate entries would have been merged, shifting the indices of the entries after them
+        if self.identifiers.len() != module.identifiers().len()
+            || self.address_identifiers.len() != module.address_identifiers().len()
+            || self.signatures.len() != module.signatures().len()
+        {
+            bail!(
+                "Cannot seed pools from module {} with duplicate pool entries",
+                module.self_id()
+            );
+        }
+
+        Ok(())
+    }
+
+    // Every pool entry carries the index it was assigned on insertion, and the compiler walks
+    // the module in a fixed order, so the materialized pools do not depend on the iteration
+    // order of the maps backing them.
     fn materialize_pool<T: Clone>(
         size: usize,
         items: impl IntoIterator<Item = (T, TableIndex)>,
@@ -349,40 +658,74 @@ impl<'a> Context<'a> {
         Self::materialize_pool(m.len(), m)
     }
 
+    /// The current size of each pool.
+    pub fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            module_handles: self.module_handles.len(),
+            struct_handles: self.struct_handles.len(),
+            function_handles: self.function_handles.len(),
+            field_handles: self.field_handles.len(),
+            signatures: self.signatures.len(),
+            identifiers: self.identifiers.len(),
+            address_identifiers: self.address_identifiers.len(),
+            constants: self.constant_pool.len(),
+            struct_instantiations: self.struct_instantiations.len(),
+            function_instantiations: self.function_instantiations.len(),
+            field_instantiations: self.field_instantiations.len(),
+        }
+    }
+
     /// Finish compilation, and materialize the pools for file format.
-    pub fn materialize_pools(self) -> (MaterializedPools, CompiledDependencies<'a>, SourceMap) {
+    /// The pools are taken out of the context, which must be reset with `reset_for_next_unit`
+    /// before compiling another unit.
+    pub fn materialize_pools(&mut self) -> (MaterializedPools, SourceMap) {
         let num_functions = self.function_handles.len();
         assert!(num_functions == self.function_signatures.len());
         let function_handles = Self::materialize_pool(
             num_functions,
-            self.function_handles
+            std::mem::take(&mut self.function_handles)
                 .into_iter()
                 .map(|(_, (t, idx))| (t, idx.0)),
         );
         let materialized_pools = MaterializedPools {
             function_handles,
-            module_handles: Self::materialize_map(self.module_handles),
-            struct_handles: Self::materialize_map(self.struct_handles),
-            field_handles: Self::materialize_map(self.field_handles),
-            signatures: Self::materialize_map(self.signatures),
-            identifiers: Self::materialize_map(self.identifiers),
-            address_identifiers: Self::materialize_map(self.address_identifiers),
-            constant_pool: Self::materialize_map(self.constant_pool),
-            function_instantiations: Self::materialize_map(self.function_instantiations),
-            struct_def_instantiations: Self::materialize_map(self.struct_instantiations),
-            field_instantiations: Self::materialize_map(self.field_instantiations),
+            module_handles: Self::materialize_map(std::mem::take(&mut self.module_handles)),
+            struct_handles: Self::materialize_map(std::mem::take(&mut self.struct_handles)),
+            field_handles: Self::materialize_map(std::mem::take(&mut self.field_handles)),
+            signatures: Self::materialize_map(std::mem::take(&mut self.signatures)),
+            identifiers: Self::materialize_map(std::mem::take(&mut self.identifiers)),
+            address_identifiers: Self::materialize_map(std::mem::take(
+                &mut self.address_identifiers,
+            )),
+            constant_pool: Self::materialize_map(std::mem::take(&mut self.constant_pool)),
+            function_instantiations: Self::materialize_map(std::mem::take(
+                &mut self.function_instantiations,
+            )),
+            struct_def_instantiations: Self::materialize_map(std::mem::take(
+                &mut self.struct_instantiations,
+            )),
+            field_instantiations: Self::materialize_map(std::mem::take(
+                &mut self.field_instantiations,
+            )),
         };
-        (materialized_pools, self.dependencies, self.source_map)
+        let empty_source_map = SourceMap::new(self.decl_location(), self.current_module);
+        let source_map = std::mem::replace(&mut self.source_map, empty_source_map);
+        (materialized_pools, source_map)
     }
 
+    /// Maps the fake offsets handed out by `label_index` to the actual offsets of the labels,
+    /// fails if a label was never registered.
     pub fn build_index_remapping(
         &mut self,
         label_to_index: HashMap<BlockLabel_, u16>,
-    ) -> HashMap<u16, u16> {
+    ) -> Result<HashMap<u16, u16>> {
         let labels = std::mem::take(&mut self.labels);
         label_to_index
             .into_iter()
-            .map(|(lbl, actual_idx)| (labels[&lbl], actual_idx))
+            .map(|(lbl, actual_idx)| match labels.get(&lbl) {
+                None => bail!("Unregistered label {}", lbl),
+                Some(fake_idx) => Ok((*fake_idx, actual_idx)),
+            })
             .collect()
     }
 
@@ -397,30 +740,35 @@ impl<'a> Context<'a> {
             .ok_or_else(|| format_err!("Missing import for module {}", ident))
     }
 
-    /// Get the handle for the alias, fails if it is not bound.
-    fn module_handle(&self, module_name: &ModuleName) -> Result<&ModuleHandle> {
+    /// Get the handle for the alias used at `loc`, fails if it is not bound.
+    fn module_handle(&self, module_name: &ModuleName, loc: Loc) -> Result<&ModuleHandle> {
         match self.modules.get(module_name) {
-            None => bail!("Unbound module alias {}", module_name),
+            None => Err(unbound_module_alias(module_name, loc)),
             Some((_, mh)) => Ok(mh),
         }
     }
 
-    /// Get the identifier for the alias, fails if it is not bound.
-    pub fn module_ident(&self, module_name: &ModuleName) -> Result<&ModuleIdent> {
+    /// Get the identifier for the alias used at `loc`, fails if it is not bound.
+    pub fn module_ident(&self, module_name: &ModuleName, loc: Loc) -> Result<&ModuleIdent> {
         match self.modules.get(module_name) {
-            None => bail!("Unbound module alias {}", module_name),
+            None => Err(unbound_module_alias(module_name, loc)),
             Some((id, _)) => Ok(id),
         }
     }
 
-    /// Get the module handle index for the alias, fails if it is not bound.
-    pub fn module_handle_index(&self, module_name: &ModuleName) -> Result<ModuleHandleIndex> {
-        Ok(ModuleHandleIndex(
-            *self
-                .module_handles
-                .get(self.module_handle(module_name)?)
-                .unwrap(),
-        ))
+    /// Get the module handle index for the alias used at `loc`, fails if it is not bound.
+    pub fn module_handle_index(
+        &self,
+        module_name: &ModuleName,
+        loc: Loc,
+    ) -> Result<ModuleHandleIndex> {
+        match self
+            .module_handles
+            .get(self.module_handle(module_name, loc)?)
+        {
+            None => bail!("Missing module handle for module alias {}", module_name),
+            Some(idx) => Ok(ModuleHandleIndex(*idx)),
+        }
     }
 
     /// Get the field handle index for the alias, adds it if missing.
@@ -433,6 +781,7 @@ impl<'a> Context<'a> {
         Ok(FieldHandleIndex(get_or_add_item(
             &mut self.field_handles,
             field_handle,
+            "field handle",
         )?))
     }
 
@@ -449,6 +798,7 @@ impl<'a> Context<'a> {
         Ok(StructDefInstantiationIndex(get_or_add_item(
             &mut self.struct_instantiations,
             struct_inst,
+            "struct instantiation",
         )?))
     }
 
@@ -465,6 +815,7 @@ impl<'a> Context<'a> {
         Ok(FunctionInstantiationIndex(get_or_add_item(
             &mut self.function_instantiations,
             func_inst,
+            "function instantiation",
         )?))
     }
 
@@ -481,36 +832,57 @@ impl<'a> Context<'a> {
         Ok(FieldInstantiationIndex(get_or_add_item(
             &mut self.field_instantiations,
             field_inst,
+            "field instantiation",
         )?))
     }
 
     /// Get the fake offset for the label. Labels will be fixed to real offsets after compilation
     pub fn label_index(&mut self, label: BlockLabel_) -> Result<CodeOffset> {
-        get_or_add_item(&mut self.labels, label)
+        get_or_add_item(&mut self.labels, label, "label")
     }
 
     /// Get the identifier pool index, adds it if missing.
     pub fn identifier_index(&mut self, s: impl AsRef<str>) -> Result<IdentifierIndex> {
         let ident = ident_str(s.as_ref())?;
         let m = &mut self.identifiers;
-        let idx: Result<TableIndex> = get_or_add_item_macro!(m, ident, ident.to_owned());
+        let idx: Result<TableIndex> =
+            get_or_add_item_macro!(m, ident, ident.to_owned(), "identifier");
         Ok(IdentifierIndex(idx?))
     }
 
+    /// Adds `ids` to the identifier pool up front, growing it once for all of them, so that
+    /// `identifier_index` finds them there. The ones missing take the next indices, in the order
+    /// given, which shifts the indices the identifiers added later would otherwise get. They stay
+    /// in the pool, and so in the compiled unit, whether or not the unit uses them.
+    pub fn intern_identifiers<'s>(&mut self, ids: impl IntoIterator<Item = &'s str>) -> Result<()> {
+        let ids = ids.into_iter();
+        self.identifiers.reserve(ids.size_hint().0);
+        for id in ids {
+            self.identifier_index(id)?;
+        }
+        Ok(())
+    }
+
     /// Get the address pool index, adds it if missing.
     pub fn address_index(&mut self, addr: AccountAddress) -> Result<AddressIdentifierIndex> {
         Ok(AddressIdentifierIndex(get_or_add_item(
             &mut self.address_identifiers,
             addr,
+            "address identifier",
         )?))
     }
 
     /// Get the byte array pool index, adds it if missing.
+    /// Constants are keyed on both their type and their serialized value, so equal values share
+    /// an index as long as they have the same type.
+    /// Fails if the serialized value is larger than the maximum constant size.
     #[allow(clippy::ptr_arg)]
     pub fn constant_index(&mut self, constant: Constant) -> Result<ConstantPoolIndex> {
+        self.check_constant_size(None, &constant)?;
         Ok(ConstantPoolIndex(get_or_add_item(
             &mut self.constant_pool,
             constant,
+            "constant",
         )?))
     }
 
@@ -525,25 +897,44 @@ impl<'a> Context<'a> {
     pub fn field(
         &self,
         s: StructHandleIndex,
-        f: Field_,
+        f: &Field_,
+        loc: Loc,
     ) -> Result<(StructDefinitionIndex, SignatureToken, usize)> {
-        match self.fields.get(&(s, f.clone())) {
-            None => bail!("Unbound field {}", f),
-            Some((sd_idx, token, decl_order)) => Ok((*sd_idx, token.clone(), *decl_order)),
+        let declared = self
+            .fields
+            .get(&s)
+            .and_then(|fields| Some((fields, *fields.order.get(f)?)));
+        match declared {
+            None => Err(LocatedError::new(loc, format!("Unbound field {}", f)).into()),
+            Some((fields, decl_order)) => Ok((
+                fields.def_idx,
+                fields.tokens[decl_order].clone(),
+                decl_order,
+            )),
         }
     }
 
     /// Get the struct definition index, fails if it is not bound.
-    pub fn struct_definition_index(&self, s: &StructName) -> Result<StructDefinitionIndex> {
+    pub fn struct_definition_index(
+        &self,
+        s: &StructName,
+        loc: Loc,
+    ) -> Result<StructDefinitionIndex> {
         match self.struct_defs.get(s) {
-            None => bail!("Missing struct definition for {}", s),
+            None => {
+                Err(LocatedError::new(loc, format!("Missing struct definition for {}", s)).into())
+            }
             Some(idx) => Ok(StructDefinitionIndex(*idx)),
         }
     }
 
     /// Get the signature pool index, adds it if missing.
     pub fn signature_index(&mut self, sig: Signature) -> Result<SignatureIndex> {
-        Ok(SignatureIndex(get_or_add_item(&mut self.signatures, sig)?))
+        Ok(SignatureIndex(get_or_add_item(
+            &mut self.signatures,
+            sig,
+            "signature",
+        )?))
     }
 
     pub fn set_function_index(&mut self, index: TableIndex) {
@@ -571,12 +962,20 @@ impl<'a> Context<'a> {
     }
 
     /// Add an import. This creates a module handle index for the imported module.
+    /// Only the current module may be imported under the reserved `Self` alias.
     pub fn declare_import(
         &mut self,
         id: ModuleIdent,
         alias: ModuleName,
     ) -> Result<ModuleHandleIndex> {
-        // We don't care about duplicate aliases, if they exist
+        if alias == ModuleName::module_self() && id != self.current_module {
+            bail!(
+                "Invalid use of reserved module alias '{}' for module {}",
+                ModuleName::self_name(),
+                id
+            )
+        }
+        // Duplicate aliases are not checked, the latest import wins: see `declare_import_strict`
         self.aliases.insert(id, alias);
         let address = self.address_index(id.address)?;
         let name = self.identifier_index(id.name.0)?;
@@ -585,18 +984,38 @@ impl<'a> Context<'a> {
         Ok(ModuleHandleIndex(get_or_add_item_ref(
             &mut self.module_handles,
             &self.modules.get(&alias).unwrap().1,
+            "module handle",
         )?))
     }
 
+    /// Like `declare_import`, but fails if `alias` is already bound to a different module, rather
+    /// than letting the latest import shadow it.
+    pub fn declare_import_strict(
+        &mut self,
+        id: ModuleIdent,
+        alias: ModuleName,
+    ) -> Result<ModuleHandleIndex> {
+        match self.modules.get(&alias) {
+            Some((previous, _)) if previous != &id => bail!(
+                "Duplicate module alias {} for modules {} and {}",
+                alias,
+                previous,
+                id
+            ),
+            _ => self.declare_import(id, alias),
+        }
+    }
+
     /// Given an identifier and basic "signature" information, creates a struct handle
-    /// and adds it to the pool.
+    /// and adds it to the pool. `loc` is where the struct is declared or used.
     pub fn declare_struct_handle_index(
         &mut self,
         sname: QualifiedStructIdent,
         abilities: AbilitySet,
         type_parameters: Vec<StructTypeParameter>,
+        loc: Loc,
     ) -> Result<StructHandleIndex> {
-        self.declare_struct_handle_index_with_abilities(sname, abilities, type_parameters)
+        self.declare_struct_handle_index_with_abilities(sname, abilities, type_parameters, loc)
     }
 
     fn declare_struct_handle_index_with_abilities(
@@ -604,8 +1023,17 @@ impl<'a> Context<'a> {
         sname: QualifiedStructIdent,
         abilities: AbilitySet,
         type_parameters: Vec<StructTypeParameter>,
+        loc: Loc,
     ) -> Result<StructHandleIndex> {
-        let module = self.module_handle_index(&sname.module)?;
+        if type_parameters.len() > MAX_TYPE_PARAMETERS {
+            bail!(
+                "Struct {} has {} type parameters, exceeding the maximum of {}",
+                sname,
+                type_parameters.len(),
+                MAX_TYPE_PARAMETERS
+            )
+        }
+        let module = self.module_handle_index(&sname.module, loc)?;
         let name = self.identifier_index(sname.name.0)?;
         self.structs.insert(
             sname.clone(),
@@ -619,6 +1047,7 @@ impl<'a> Context<'a> {
         Ok(StructHandleIndex(get_or_add_item_ref(
             &mut self.struct_handles,
             self.structs.get(&sname).unwrap(),
+            "struct handle",
         )?))
     }
 
@@ -626,18 +1055,35 @@ impl<'a> Context<'a> {
     pub fn declare_struct_definition_index(
         &mut self,
         s: StructName,
+        loc: Loc,
     ) -> Result<StructDefinitionIndex> {
         let idx = self.struct_defs.len();
         if idx > TABLE_MAX_SIZE {
             bail!("too many struct definitions {}", s)
         }
-        // TODO: Add the decl of the struct definition name here
-        // need to handle duplicates
+        // Unless checked here, a duplicate reuses the first index and is left to the bytecode
+        // verifier
+        if self.checks.duplicates && self.struct_defs.contains_key(&s) {
+            return Err(
+                LocatedError::new(loc, format!("Duplicate struct definition for {}", s)).into(),
+            );
+        }
         Ok(StructDefinitionIndex(
             *self.struct_defs.entry(s).or_insert(idx as TableIndex),
         ))
     }
 
+    /// The struct definitions declared so far, in definition index order.
+    pub fn struct_definitions(&self) -> impl Iterator<Item = (&StructName, StructDefinitionIndex)> {
+        let mut defs: Vec<_> = self
+            .struct_defs
+            .iter()
+            .map(|(name, idx)| (name, StructDefinitionIndex(*idx)))
+            .collect();
+        defs.sort_by_key(|(_, idx)| idx.0);
+        defs.into_iter()
+    }
+
     /// Given an identifier and a signature, creates a function handle and adds it to the pool.
     /// Finds the index for the signature, or adds it to the pool if an identical one has not yet
     /// been used.
@@ -646,9 +1092,19 @@ impl<'a> Context<'a> {
         mname: ModuleName,
         fname: FunctionName,
         signature: FunctionSignature,
+        loc: Loc,
     ) -> Result<()> {
+        if signature.type_parameters.len() > MAX_TYPE_PARAMETERS {
+            bail!(
+                "Function {}.{} has {} type parameters, exceeding the maximum of {}",
+                mname,
+                fname,
+                signature.type_parameters.len(),
+                MAX_TYPE_PARAMETERS
+            )
+        }
         let m_f = (mname, fname.clone());
-        let module = self.module_handle_index(&mname)?;
+        let module = self.module_handle_index(&mname, loc)?;
         let name = self.identifier_index(fname.0)?;
 
         self.function_signatures
@@ -660,14 +1116,14 @@ impl<'a> Context<'a> {
             type_parameters,
         } = signature;
 
-        let params_idx = get_or_add_item(&mut self.signatures, Signature(parameters))?;
-        let return_idx = get_or_add_item(&mut self.signatures, Signature(return_))?;
+        let parameters = self.signature_index(Signature(parameters))?;
+        let return_ = self.signature_index(Signature(return_))?;
 
         let handle = FunctionHandle {
             module,
             name,
-            parameters: SignatureIndex(params_idx as TableIndex),
-            return_: SignatureIndex(return_idx as TableIndex),
+            parameters,
+            return_,
             type_parameters,
         };
         // handle duplicate declarations
@@ -685,26 +1141,83 @@ impl<'a> Context<'a> {
         Ok(())
     }
 
+    /// Fails if the serialized value of the constant, named `name` if it has a name, is larger
+    /// than the maximum constant size.
+    fn check_constant_size(&self, name: Option<&ConstantName>, constant: &Constant) -> Result<()> {
+        if constant.data.len() <= self.max_constant_size {
+            return Ok(());
+        }
+        let name = name.map(|name| format!(" {}", name)).unwrap_or_default();
+        bail!(
+            "Constant{} of {} bytes exceeds the maximum constant size of {} bytes",
+            name,
+            constant.data.len(),
+            self.max_constant_size
+        )
+    }
+
     /// Given a named constant, adds it to the pool
     pub fn declare_constant(&mut self, name: ConstantName, constant: Constant) -> Result<()> {
+        self.check_constant_size(Some(&name), &constant)?;
         let idx = self.constant_index(constant)?;
         self.named_constants.insert(name, idx.0);
         Ok(())
     }
 
-    /// Given a struct handle and a field, adds it to the pool.
+    /// Declares a function without a body. Its handle is built like any other function's, but
+    /// the function is recorded as native.
+    pub fn declare_native_function(
+        &mut self,
+        mname: ModuleName,
+        fname: FunctionName,
+        signature: FunctionSignature,
+        loc: Loc,
+    ) -> Result<()> {
+        self.declare_function(mname, fname.clone(), signature, loc)?;
+        self.native_functions.insert((mname, fname));
+        Ok(())
+    }
+
+    /// Whether the function was declared as native, either in this module or in a dependency.
+    pub fn is_native_function(&self, mname: &ModuleName, fname: &FunctionName) -> bool {
+        self.native_functions.contains(&(*mname, fname.clone()))
+    }
+
+    /// Given a struct handle and a field, adds it to the pool. Fields must be declared in
+    /// declaration order. A field the struct already declares keeps its first declaration, or
+    /// fails if duplicates are checked.
     pub fn declare_field(
         &mut self,
         s: StructHandleIndex,
         sd_idx: StructDefinitionIndex,
         f: Field_,
         token: SignatureToken,
-        decl_order: usize,
-    ) {
-        // need to handle duplicates
-        self.fields
-            .entry((s, f))
-            .or_insert((sd_idx, token, decl_order));
+        loc: Loc,
+    ) -> Result<()> {
+        let fields = self.fields.entry(s).or_insert_with(|| StructFields {
+            def_idx: sd_idx,
+            tokens: vec![],
+            order: HashMap::new(),
+        });
+        if fields.order.contains_key(&f) {
+            if !self.checks.duplicates {
+                return Ok(());
+            }
+            let sname = self
+                .struct_defs
+                .iter()
+                .find(|(_, idx)| **idx == sd_idx.0)
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_default();
+            return Err(LocatedError::new(
+                loc,
+                format!("Duplicate field {} in struct {}", f, sname),
+            )
+            .into());
+        }
+        fields.order.insert(f, fields.tokens.len());
+        fields.tokens.push(token);
+        Ok(())
     }
 
     //**********************************************************************************************
@@ -725,13 +1238,14 @@ impl<'a> Context<'a> {
     fn dep_struct_handle(
         &mut self,
         s: &QualifiedStructIdent,
+        loc: Loc,
     ) -> Result<(AbilitySet, Vec<StructTypeParameter>)> {
         if s.module == ModuleName::module_self() {
             bail!("Unbound struct {}", s)
         }
-        let mident = *self.module_ident(&s.module)?;
+        let mident = *self.module_ident(&s.module, loc)?;
         let dep = self.dependency(&mident)?;
-        match dep.struct_handle(&mident.name, &s.name) {
+        match dep.struct_handle(&mident, &s.name) {
             None => bail!("Unbound struct {}", s),
             Some(shandle) => Ok((shandle.abilities, shandle.type_parameters.clone())),
         }
@@ -739,22 +1253,167 @@ impl<'a> Context<'a> {
 
     /// Given an identifier, find the struct handle index.
     /// Creates the handle and adds it to the pool if it it is the *first* time it looks
-    /// up the struct in a dependency.
-    pub fn struct_handle_index(&mut self, s: QualifiedStructIdent) -> Result<StructHandleIndex> {
-        match self.structs.get(&s) {
-            Some(sh) => Ok(StructHandleIndex(*self.struct_handles.get(sh).unwrap())),
+    /// up the struct in a dependency. `loc` is where the struct is used.
+    pub fn struct_handle_index(
+        &mut self,
+        s: QualifiedStructIdent,
+        loc: Loc,
+    ) -> Result<StructHandleIndex> {
+        self.struct_handle_index_requiring(s, AbilitySet::EMPTY, loc)
+    }
+
+    /// Like `struct_handle_index`, for a struct used where its instantiation must have the
+    /// `required` abilities. If dependency abilities are checked, fails when a dependency struct
+    /// does not declare all of them.
+    pub fn struct_handle_index_requiring(
+        &mut self,
+        s: QualifiedStructIdent,
+        required: AbilitySet,
+        loc: Loc,
+    ) -> Result<StructHandleIndex> {
+        let idx = match self.structs.get(&s) {
+            Some(sh) => StructHandleIndex(*self.struct_handles.get(sh).unwrap()),
             None => {
-                let (abilities, type_parameters) = self.dep_struct_handle(&s)?;
-                self.declare_struct_handle_index_with_abilities(s, abilities, type_parameters)
+                let (abilities, type_parameters) = self.dep_struct_handle(&s, loc)?;
+                self.declare_struct_handle_index_with_abilities(
+                    s.clone(),
+                    abilities,
+                    type_parameters,
+                    loc,
+                )?
+            }
+        };
+        if self.checks.dependency_abilities && s.module != ModuleName::module_self() {
+            self.check_struct_abilities(&s, required, loc)?;
+        }
+        Ok(idx)
+    }
+
+    /// Checks that the struct is instantiated with as many type arguments as it declares type
+    /// parameters, fails if the struct is unbound.
+    pub fn check_type_arity(
+        &self,
+        s: &QualifiedStructIdent,
+        num_type_args: usize,
+        loc: Loc,
+    ) -> Result<()> {
+        let expected = match self.structs.get(s) {
+            None => return Err(LocatedError::new(loc, format!("Unbound struct {}", s)).into()),
+            Some(handle) => handle.type_parameters.len(),
+        };
+        if expected != num_type_args {
+            return Err(LocatedError::new(
+                loc,
+                format!(
+                    "Invalid instantiation of struct {}: expected {} type argument(s), got {}",
+                    s, expected, num_type_args
+                ),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// A struct type parameter with the `constraints` abilities, phantom if `is_phantom`: it may
+    /// then only be used as the argument of phantom type parameters.
+    pub fn struct_type_parameter(constraints: AbilitySet, is_phantom: bool) -> StructTypeParameter {
+        StructTypeParameter {
+            constraints,
+            is_phantom,
+        }
+    }
+
+    /// Finds a type parameter flagged in `phantoms` that `token` uses other than as the argument
+    /// of a phantom type parameter of a struct.
+    pub fn phantom_in_non_phantom_position(
+        &self,
+        token: &SignatureToken,
+        phantoms: &[bool],
+    ) -> Option<TypeParameterIndex> {
+        match token {
+            SignatureToken::TypeParameter(idx) => phantoms
+                .get(*idx as usize)
+                .copied()
+                .unwrap_or(false)
+                .then_some(*idx),
+            SignatureToken::Vector(inner)
+            | SignatureToken::Reference(inner)
+            | SignatureToken::MutableReference(inner) => {
+                self.phantom_in_non_phantom_position(inner, phantoms)
             }
+            SignatureToken::StructInstantiation(sh_idx, tys) => {
+                let (handle, _) = self
+                    .struct_handles
+                    .iter()
+                    .find(|(_, idx)| **idx == sh_idx.0)?;
+                handle
+                    .type_parameters
+                    .iter()
+                    .zip(tys)
+                    .filter(|(type_parameter, _)| !type_parameter.is_phantom)
+                    .find_map(|(_, ty)| self.phantom_in_non_phantom_position(ty, phantoms))
+            }
+            _ => None,
+        }
+    }
+
+    /// The type parameters declared by the struct, fails if the struct is unbound.
+    pub fn struct_type_parameters(
+        &self,
+        s: &QualifiedStructIdent,
+    ) -> Result<&[StructTypeParameter]> {
+        match self.structs.get(s) {
+            None => bail!("Unbound struct {}", s),
+            Some(handle) => Ok(&handle.type_parameters),
         }
     }
 
+    /// Checks that the struct declares all the `required` abilities, fails if the struct is unbound.
+    /// An instantiation of the struct has at most its declared abilities, so a struct missing one of
+    /// them can never satisfy the requirement.
+    fn check_struct_abilities(
+        &self,
+        s: &QualifiedStructIdent,
+        required: AbilitySet,
+        loc: Loc,
+    ) -> Result<()> {
+        let abilities = match self.structs.get(s) {
+            None => bail!("Unbound struct {}", s),
+            Some(handle) => handle.abilities,
+        };
+        let missing: Vec<_> = required
+            .into_iter()
+            .filter(|ability| !abilities.has_ability(*ability))
+            .map(|ability| format!("{:?}", ability).to_lowercase())
+            .collect();
+        if !missing.is_empty() {
+            return Err(LocatedError::new(
+                loc,
+                format!(
+                    "Struct {} is missing required abilities: {}",
+                    s,
+                    missing.join(", ")
+                ),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
     fn reindex_signature_token(
         &mut self,
         dep: &ModuleIdent,
         orig: SignatureToken,
+        depth: usize,
+        loc: Loc,
     ) -> Result<SignatureToken> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!(
+                "Type in dependency {} exceeds the maximum nesting depth of {}",
+                dep,
+                MAX_TYPE_DEPTH
+            )
+        }
         Ok(match orig {
             x @ SignatureToken::Bool
             | x @ SignatureToken::U8
@@ -767,15 +1426,15 @@ impl<'a> Context<'a> {
             | x @ SignatureToken::Signer
             | x @ SignatureToken::TypeParameter(_) => x,
             SignatureToken::Vector(inner) => {
-                let correct_inner = self.reindex_signature_token(dep, *inner)?;
+                let correct_inner = self.reindex_signature_token(dep, *inner, depth + 1, loc)?;
                 SignatureToken::Vector(Box::new(correct_inner))
             }
             SignatureToken::Reference(inner) => {
-                let correct_inner = self.reindex_signature_token(dep, *inner)?;
+                let correct_inner = self.reindex_signature_token(dep, *inner, depth + 1, loc)?;
                 SignatureToken::Reference(Box::new(correct_inner))
             }
             SignatureToken::MutableReference(inner) => {
-                let correct_inner = self.reindex_signature_token(dep, *inner)?;
+                let correct_inner = self.reindex_signature_token(dep, *inner, depth + 1, loc)?;
                 SignatureToken::MutableReference(Box::new(correct_inner))
             }
             SignatureToken::Struct(orig_sh_idx) => {
@@ -788,7 +1447,7 @@ impl<'a> Context<'a> {
                     module: module_name,
                     name: sname,
                 };
-                let correct_sh_idx = self.struct_handle_index(sident)?;
+                let correct_sh_idx = self.struct_handle_index(sident, loc)?;
                 SignatureToken::Struct(correct_sh_idx)
             }
             SignatureToken::StructInstantiation(orig_sh_idx, inners) => {
@@ -801,32 +1460,53 @@ impl<'a> Context<'a> {
                     module: module_name,
                     name: sname,
                 };
-                let correct_sh_idx = self.struct_handle_index(sident)?;
+                let correct_sh_idx = self.struct_handle_index(sident.clone(), loc)?;
+                if self.checks.struct_type_arity {
+                    self.check_type_arity(&sident, inners.len(), loc)?;
+                }
                 let correct_inners = inners
                     .into_iter()
-                    .map(|t| self.reindex_signature_token(dep, t))
+                    .map(|t| self.reindex_signature_token(dep, t, depth + 1, loc))
                     .collect::<Result<_>>()?;
                 SignatureToken::StructInstantiation(correct_sh_idx, correct_inners)
             }
         })
     }
 
+    /// Reindexes a signature token of the dependency, reusing the result of any previous
+    /// reindexing of the same token. Dependencies do not change during a compilation unit.
+    fn reindexed_signature_token(
+        &mut self,
+        dep: &ModuleIdent,
+        orig: SignatureToken,
+        loc: Loc,
+    ) -> Result<SignatureToken> {
+        let key = (*dep, orig);
+        if let Some(token) = self.reindexed_tokens.get(&key) {
+            return Ok(token.clone());
+        }
+        let token = self.reindex_signature_token(dep, key.1.clone(), 0, loc)?;
+        self.reindexed_tokens.insert(key, token.clone());
+        Ok(token)
+    }
+
     fn reindex_function_signature(
         &mut self,
         dep: &ModuleIdent,
-        orig: FunctionSignature,
+        orig: &FunctionSignature,
+        loc: Loc,
     ) -> Result<FunctionSignature> {
         let return_ = orig
             .return_
-            .into_iter()
-            .map(|t| self.reindex_signature_token(dep, t))
+            .iter()
+            .map(|t| self.reindexed_signature_token(dep, t.clone(), loc))
             .collect::<Result<_>>()?;
         let parameters = orig
             .parameters
-            .into_iter()
-            .map(|t| self.reindex_signature_token(dep, t))
+            .iter()
+            .map(|t| self.reindexed_signature_token(dep, t.clone(), loc))
             .collect::<Result<_>>()?;
-        let type_parameters = orig.type_parameters;
+        let type_parameters = orig.type_parameters.clone();
         Ok(FunctionSignature {
             return_,
             parameters,
@@ -838,24 +1518,34 @@ impl<'a> Context<'a> {
         &mut self,
         m: &ModuleName,
         f: &FunctionName,
+        loc: Loc,
     ) -> Result<FunctionSignature> {
         if m == &ModuleName::module_self() {
             bail!("Unbound function {}.{}", m, f)
         }
-        let mident = *self.module_ident(m)?;
+        let mident = *self.module_ident(m, loc)?;
         let dep = self.dependency(&mident)?;
         match dep.function_signature(f) {
             None => bail!("Unbound function {}.{}", mident, f),
-            Some(sig) => self.reindex_function_signature(&mident, sig),
+            Some(sig) => self.reindex_function_signature(&mident, &sig, loc),
         }
     }
 
-    fn ensure_function_declared(&mut self, m: ModuleName, f: FunctionName) -> Result<()> {
+    fn dep_function_is_native(&self, m: &ModuleName, f: &FunctionName, loc: Loc) -> Result<bool> {
+        let mident = *self.module_ident(m, loc)?;
+        Ok(self.dependency(&mident)?.is_native_function(f))
+    }
+
+    fn ensure_function_declared(&mut self, m: ModuleName, f: FunctionName, loc: Loc) -> Result<()> {
         let m_f = (m, f.clone());
         if !self.function_handles.contains_key(&m_f) {
             assert!(!self.function_signatures.contains_key(&m_f));
-            let sig = self.dep_function_signature(&m, &f)?;
-            self.declare_function(m, f, sig)?;
+            let sig = self.dep_function_signature(&m, &f, loc)?;
+            if self.dep_function_is_native(&m, &f, loc)? {
+                self.declare_native_function(m, f, sig, loc)?;
+            } else {
+                self.declare_function(m, f, sig, loc)?;
+            }
         }
 
         assert!(self.function_handles.contains_key(&m_f));
@@ -865,17 +1555,198 @@ impl<'a> Context<'a> {
 
     /// Given an identifier, find the function handle and its index.
     /// Creates the handle+signature and adds it to the pool if it it is the *first* time it looks
-    /// up the function in a dependency.
+    /// up the function in a dependency. `loc` is where the function is used.
     pub fn function_handle(
         &mut self,
         m: ModuleName,
         f: FunctionName,
+        loc: Loc,
     ) -> Result<&(FunctionHandle, FunctionHandleIndex)> {
-        self.ensure_function_declared(m, f.clone())?;
+        self.ensure_function_declared(m, f.clone(), loc)?;
         Ok(self.function_handles.get(&(m, f)).unwrap())
     }
 
+    /// Get the signature of a function that has already been declared, without declaring it.
+    pub fn function_signature(
+        &self,
+        m: &ModuleName,
+        f: &FunctionName,
+    ) -> Option<&FunctionSignature> {
+        self.function_signatures.get(&(*m, f.clone()))
+    }
+
+    /// The source map built so far. Compilation can carry on after inspecting it, until
+    /// `materialize_pools` takes it out of the context.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
     pub fn decl_location(&self) -> Loc {
         self.source_map.definition_location
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::empty_module;
+    use move_symbol_pool::Symbol;
+
+    fn test_context() -> Context<'static> {
+        let current_module = ModuleIdent::new(ModuleName(Symbol::from("M")), AccountAddress::ONE);
+        Context::new(Loc::invalid(), HashMap::new(), current_module).unwrap()
+    }
+
+    #[test]
+    fn remap_registered_labels() {
+        let mut context = test_context();
+        let b0 = BlockLabel_(Symbol::from("b0"));
+        let b1 = BlockLabel_(Symbol::from("b1"));
+        let fake_b0 = context.label_index(b0.clone()).unwrap();
+        let fake_b1 = context.label_index(b1.clone()).unwrap();
+
+        let remapping = context
+            .build_index_remapping(HashMap::from([(b0, 0), (b1, 5)]))
+            .unwrap();
+        assert_eq!(remapping, HashMap::from([(fake_b0, 0), (fake_b1, 5)]));
+    }
+
+    #[test]
+    fn remap_dangling_label() {
+        let mut context = test_context();
+        context
+            .label_index(BlockLabel_(Symbol::from("b0")))
+            .unwrap();
+
+        let err = context
+            .build_index_remapping(HashMap::from([(BlockLabel_(Symbol::from("b1")), 0)]))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unregistered label b1");
+    }
+
+    #[test]
+    fn module_handle_index_after_materialization() {
+        let mut context = test_context();
+        let alias = ModuleName(Symbol::from("D"));
+        let dep_ident = ModuleIdent::new(alias, AccountAddress::ONE);
+        context.declare_import(dep_ident, alias).unwrap();
+        assert!(context.module_handle_index(&alias, Loc::invalid()).is_ok());
+
+        // The alias is still bound, but its handle went away with the pools
+        context.materialize_pools();
+        let err = context
+            .module_handle_index(&alias, Loc::invalid())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Missing module handle for module alias D");
+    }
+
+    #[test]
+    fn pool_stats() {
+        let mut context = test_context();
+        context.identifier_index("foo").unwrap();
+        context.identifier_index("foo").unwrap();
+        context.identifier_index("bar").unwrap();
+        context.signature_index(Signature(vec![])).unwrap();
+
+        let stats = context.pool_stats();
+        assert_eq!(
+            stats,
+            PoolStats {
+                identifiers: 2,
+                signatures: 1,
+                ..PoolStats::default()
+            }
+        );
+
+        let (pool, identifiers_full) = stats.percent_full()[5];
+        assert_eq!(pool, "identifier");
+        assert_eq!(identifiers_full, 200.0 / TABLE_MAX_SIZE as f64);
+    }
+
+    #[test]
+    fn reset_for_next_unit() {
+        let mut context = test_context();
+        let dep_ident = ModuleIdent::new(ModuleName(Symbol::from("D")), AccountAddress::ONE);
+        context
+            .add_stored_dependency(dep_ident, empty_module())
+            .unwrap();
+        context.set_max_constant_size(1);
+        let checks = CompileChecks {
+            duplicates: true,
+            ..CompileChecks::default()
+        };
+        context.set_checks(checks);
+        context.identifier_index("foo").unwrap();
+        context.signature_index(Signature(vec![])).unwrap();
+
+        let next_module = ModuleIdent::new(ModuleName(Symbol::from("N")), AccountAddress::ONE);
+        context
+            .reset_for_next_unit(Loc::invalid(), next_module)
+            .unwrap();
+        assert_eq!(context.pool_stats(), PoolStats::default());
+        assert_eq!(context.current_module, next_module);
+
+        assert!(context.dependency(&dep_ident).is_ok());
+        assert_eq!(context.checks(), checks);
+        let constant = |len| Constant {
+            type_: SignatureToken::Vector(Box::new(SignatureToken::U8)),
+            data: vec![0; len],
+        };
+        assert!(context.constant_index(constant(1)).is_ok());
+        assert!(context.constant_index(constant(2)).is_err());
+    }
+
+    #[test]
+    fn named_constant_above_max_size() {
+        let mut context = test_context();
+        context.set_max_constant_size(1);
+        let constant = Constant {
+            type_: SignatureToken::Vector(Box::new(SignatureToken::U8)),
+            data: vec![0; 2],
+        };
+
+        let err = context
+            .declare_constant(ConstantName(Symbol::from("BLOB")), constant)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Constant BLOB of 2 bytes exceeds the maximum constant size of 1 bytes"
+        );
+        assert_eq!(context.pool_stats().constants, 0);
+    }
+
+    #[test]
+    fn native_dependency_function() {
+        let code = "
+            module 0x42.A {
+                native public f(x: u64): u64;
+                public g() {
+                label b0:
+                    return;
+                }
+            }
+            ";
+        let no_deps: Vec<CompiledModule> = vec![];
+        let (dep, _) =
+            crate::compiler::compile_module(crate::parser::parse_module(code).unwrap(), &no_deps)
+                .unwrap();
+
+        let mut context = test_context();
+        let alias = ModuleName(Symbol::from("A"));
+        let dep_ident = ModuleIdent::new(alias, AccountAddress::from_hex_literal("0x42").unwrap());
+        context.add_stored_dependency(dep_ident, dep).unwrap();
+        context.declare_import(dep_ident, alias).unwrap();
+
+        let f = FunctionName(Symbol::from("f"));
+        let g = FunctionName(Symbol::from("g"));
+        assert!(!context.is_native_function(&alias, &f));
+        context
+            .function_handle(alias, f.clone(), Loc::invalid())
+            .unwrap();
+        context
+            .function_handle(alias, g.clone(), Loc::invalid())
+            .unwrap();
+        assert!(context.is_native_function(&alias, &f));
+        assert!(!context.is_native_function(&alias, &g));
+    }
+}
@@ -8,4 +8,6 @@ pub mod compiler;
 mod context;
 pub mod parser;
 
+pub use context::{CompileChecks, LocatedError, PoolStats, DEFAULT_MAX_CONSTANT_SIZE};
+
 // Unit tests for this crate are in the parent "compiler" crate.